@@ -0,0 +1,58 @@
+//! A uniform grid for finding nearby colliders without testing every pair of entities.
+
+use std::collections::HashMap;
+
+use crate::components::{Collider, Pos};
+
+pub struct Broadphase {
+    cell_size: i32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Broadphase {
+    /// Constructs an empty `Broadphase` with square cells of the given size.
+    pub fn new(cell_size: i32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Returns the coordinates of every cell that a box overlaps.
+    fn cells(&self, pos: Pos, collider: Collider) -> impl Iterator<Item = (i32, i32)> {
+        let x1 = pos.x.div_euclid(self.cell_size);
+        let y1 = pos.y.div_euclid(self.cell_size);
+        let x2 = (pos.x + collider.w).div_euclid(self.cell_size);
+        let y2 = (pos.y + collider.h).div_euclid(self.cell_size);
+        (y1..=y2).flat_map(move |y| (x1..=x2).map(move |x| (x, y)))
+    }
+
+    /// Adds an entity to every cell its collider overlaps.
+    pub fn insert(&mut self, entity: usize, pos: Pos, collider: Collider) {
+        for cell in self.cells(pos, collider).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+    }
+
+    /// Removes an entity previously inserted with the same position and collider.
+    pub fn remove(&mut self, entity: usize, pos: Pos, collider: Collider) {
+        for cell in self.cells(pos, collider).collect::<Vec<_>>() {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.retain(|&other| other != entity);
+            }
+        }
+    }
+
+    /// Returns every entity sharing a cell with the given box, without duplicates.
+    pub fn query(&self, pos: Pos, collider: Collider) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .cells(pos, collider)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
@@ -1,5 +1,6 @@
 use crate::ecs::{Component, World};
-use crate::Context;
+use crate::util::round_px;
+use crate::{Context, SUBPIXELS};
 
 pub fn register_components(world: &mut World<Context>) {
     world.register::<Pos>();
@@ -11,6 +12,28 @@ pub fn register_components(world: &mut World<Context>) {
     world.register::<HealthMod>();
     world.register::<Follow>();
     world.register::<Push>();
+    world.register::<PressurePlate>();
+    world.register::<Switch>();
+    world.register::<Gate>();
+    world.register::<Falling>();
+    world.register::<Solid>();
+    world.register::<Pusher>();
+    world.register::<Trigger>();
+    world.register::<Nudge>();
+    world.register::<IFrames>();
+    world.register::<Resistances>();
+    world.register::<Projectile>();
+    world.register::<Shooter>();
+    world.register::<Bomb>();
+    world.register::<Explosion>();
+    world.register::<Loot>();
+    world.register::<Pickup>();
+    world.register::<StatusEffects>();
+    world.register::<Fire>();
+    world.register::<Dying>();
+    world.register::<Checkpoint>();
+    world.register::<Boss>();
+    world.register::<Invulnerable>();
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -22,6 +45,11 @@ impl Pos {
     pub fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    /// Returns the position rounded to whole pixels, for rendering.
+    pub fn px(&self) -> (i32, i32) {
+        (round_px(self.x), round_px(self.y))
+    }
 }
 impl Component for Pos {}
 
@@ -40,35 +68,488 @@ impl Component for Vel {}
 pub struct Spr(pub i32);
 impl Component for Spr {}
 
-#[derive(Default)]
 pub struct Player {
-    pub attack: bool,
+    /// The cardinal direction the player last moved in, which swings are aimed along.
+    pub facing: (i32, i32),
+    pub swing: Option<Swing>,
+    pub safe_pos: Option<Pos>,
+    pub coins: u32,
+    pub arrows: u32,
+    pub bombs: u32,
+}
+impl Player {
+    /// Returns the area the player's sword covers, while a swing is in its active frames.
+    pub fn hitbox(&self, pos: Pos, collider: Collider) -> Option<(Pos, Collider)> {
+        if !self.swing.as_ref()?.active() {
+            return None;
+        }
+        let (fx, fy) = self.facing;
+        let along = |start: i32, size: i32, dir: i32| match dir {
+            1 => (start + size + 1, SWING_REACH),
+            -1 => (start - 1 - SWING_REACH, SWING_REACH),
+            _ => (start, size),
+        };
+        let (x, w) = along(pos.x, collider.w, fx);
+        let (y, h) = along(pos.y, collider.h, fy);
+        Some((Pos::new(x, y), Collider::new(w, h)))
+    }
+}
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            facing: (0, 1),
+            swing: None,
+            safe_pos: None,
+            coins: 0,
+            arrows: 0,
+            bombs: 0,
+        }
+    }
+}
+impl Component for Player {
+    fn forget(&mut self, entity: usize) {
+        if let Some(swing) = &mut self.swing {
+            swing.hit.retain(|&i| i != entity);
+        }
+    }
+}
+
+pub const SWING_WINDUP_TICKS: u32 = 5;
+pub const SWING_ACTIVE_TICKS: u32 = 6;
+pub const SWING_RECOVERY_TICKS: u32 = 10;
+/// How far in front of the player a swing reaches.
+pub const SWING_REACH: i32 = 12 * SUBPIXELS;
+
+/// A sword swing in progress, from wind-up through to recovery.
+#[derive(Default)]
+pub struct Swing {
+    pub ticks: u32,
+    /// Entities already struck by this swing, which it won't hit again.
+    pub hit: Vec<usize>,
+}
+impl Swing {
+    pub fn active(&self) -> bool {
+        (SWING_WINDUP_TICKS..SWING_WINDUP_TICKS + SWING_ACTIVE_TICKS).contains(&self.ticks)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.ticks >= SWING_WINDUP_TICKS + SWING_ACTIVE_TICKS + SWING_RECOVERY_TICKS
+    }
+}
+
+/// Collision layers, combined as bit flags in a [`Collider`]'s `layer` and `mask`.
+pub mod layer {
+    pub const WORLD: u32 = 1 << 0;
+    pub const PLAYER: u32 = 1 << 1;
+    pub const ENEMY: u32 = 1 << 2;
+    pub const PROJECTILE: u32 = 1 << 3;
+    pub const PICKUP: u32 = 1 << 4;
+    pub const TRIGGER: u32 = 1 << 5;
+    pub const ALL: u32 = u32::MAX;
+}
+
+/// The geometry a [`Collider`] fills within its `w` by `h` bounding box.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Aabb,
+    /// A circle inscribed in the bounding box, with a radius of half its width.
+    Circle,
 }
-impl Component for Player {}
 
 #[derive(Clone, Copy)]
 pub struct Collider {
     pub w: i32,
     pub h: i32,
+    pub shape: Shape,
+    pub layer: u32,
+    pub mask: u32,
 }
 impl Collider {
     pub fn new(w: i32, h: i32) -> Self {
-        Self { w, h }
+        Self {
+            w,
+            h,
+            shape: Shape::Aabb,
+            layer: layer::WORLD,
+            mask: layer::ALL,
+        }
+    }
+
+    pub fn circle(radius: i32) -> Self {
+        Self {
+            shape: Shape::Circle,
+            ..Self::new(radius * 2, radius * 2)
+        }
+    }
+
+    /// Checks whether this collider's mask includes the other collider's layer.
+    pub fn collides_with(&self, other: &Collider) -> bool {
+        self.mask & other.layer != 0
     }
 }
 impl Component for Collider {}
 
-pub struct Health(pub i32);
+/// The units of [`Health`] in a whole heart.
+pub const HALF_HEARTS: i32 = 2;
+
+/// Hit points, counted in half-hearts.
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+impl Health {
+    /// Restores health, without going over the maximum.
+    pub fn heal(&mut self, amount: i32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+}
 impl Component for Health {}
 
 pub struct HealthMod {
+    /// The health given on contact, in half-hearts, or taken when negative.
     pub health: i32,
-    pub cooldown: u32,
+    /// The kind of damage dealt, when `health` is negative.
+    pub kind: DamageKind,
+    pub status: Option<(Status, u32)>,
 }
 impl Component for HealthMod {}
 
-pub struct Follow(pub usize);
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DamageKind {
+    Slash,
+    Fire,
+    Explosive,
+    Fall,
+    Poison,
+}
+
+#[derive(Clone, Copy)]
+pub struct Damage {
+    /// How many half-hearts the hit takes.
+    pub amount: i32,
+    pub kind: DamageKind,
+    /// A status effect, and how many ticks it lasts, inflicted whenever the hit lands.
+    pub status: Option<(Status, u32)>,
+}
+impl Damage {
+    pub fn new(amount: i32, kind: DamageKind) -> Self {
+        Self {
+            amount,
+            kind,
+            status: None,
+        }
+    }
+
+    pub fn with_status(self, status: Status, ticks: u32) -> Self {
+        Self {
+            status: Some((status, ticks)),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Deals damage every so often, harder with each stack.
+    Poison,
+    /// Stops the entity acting on its own.
+    Stun,
+    /// Stops the entity acting or moving at all.
+    Freeze,
+    /// Deals fire damage every so often, and sets flammable floors alight.
+    Burn,
+}
+
+/// The most stacks of poison an entity can suffer at once.
+pub const MAX_POISON_STACKS: u32 = 3;
+
+#[derive(Clone, Copy)]
+pub struct Effect {
+    pub status: Status,
+    pub ticks: u32,
+    pub stacks: u32,
+}
+
+/// The status effects currently afflicting an entity.
+#[derive(Default)]
+pub struct StatusEffects(pub Vec<Effect>);
+impl StatusEffects {
+    /// Adds an effect. Reapplying one keeps whichever duration is longer, and poison also gains a
+    /// stack each time.
+    pub fn apply(&mut self, status: Status, ticks: u32) {
+        match self.0.iter_mut().find(|effect| effect.status == status) {
+            Some(effect) => {
+                effect.ticks = effect.ticks.max(ticks);
+                if status == Status::Poison {
+                    effect.stacks = (effect.stacks + 1).min(MAX_POISON_STACKS);
+                }
+            }
+            None => self.0.push(Effect {
+                status,
+                ticks,
+                stacks: 1,
+            }),
+        }
+    }
+
+    pub fn has(&self, status: Status) -> bool {
+        self.0.iter().any(|effect| effect.status == status)
+    }
+
+    /// Checks whether the entity is stunned or frozen, and so can't act.
+    pub fn incapacitated(&self) -> bool {
+        self.has(Status::Stun) || self.has(Status::Freeze)
+    }
+}
+impl Component for StatusEffects {}
+
+/// A burning tile, which sets whatever touches it alight and spreads to flammable neighbours.
+pub struct Fire {
+    pub ticks: u32,
+}
+impl Component for Fire {}
+
+/// Which kinds of damage an entity shrugs off, takes half of, or takes double from.
+#[derive(Default)]
+pub struct Resistances {
+    pub immune: Vec<DamageKind>,
+    pub resist: Vec<DamageKind>,
+    pub weak: Vec<DamageKind>,
+}
+impl Resistances {
+    /// Works out how much health a hit actually takes. Resisted hits still take at least one.
+    pub fn apply(&self, damage: Damage) -> i32 {
+        if self.immune.contains(&damage.kind) {
+            0
+        } else if self.resist.contains(&damage.kind) {
+            (damage.amount + 1) / 2
+        } else if self.weak.contains(&damage.kind) {
+            damage.amount * 2
+        } else {
+            damage.amount
+        }
+    }
+}
+impl Component for Resistances {}
+
+/// How long an entity stays invulnerable after taking damage, overriding the default.
+#[derive(Clone, Copy)]
+pub struct IFrames {
+    pub ticks: u32,
+}
+impl Component for IFrames {}
+
+/// Marks an entity that recently took damage and ignores further hits until `ticks` runs out.
+pub struct Invulnerable {
+    pub ticks: u32,
+}
+impl Component for Invulnerable {}
+
+pub struct Follow {
+    pub target: usize,
+    pub speed: i32,
+}
 impl Component for Follow {}
 
-pub struct Push;
+/// A block that slides one tile after being pushed against for a while, along with any row of
+/// blocks in front of it up to `max_chain` long.
+pub struct Push {
+    pub once: bool,
+    pub pushed: bool,
+    pub directions: Vec<(i32, i32)>,
+    pub max_chain: usize,
+    /// The direction currently being pushed or slid in.
+    pub dir: (i32, i32),
+    /// How many ticks the block has been pushed in the current direction.
+    pub progress: u32,
+    /// How much further the block has to slide, in fixed-point units.
+    pub slide: i32,
+}
+impl Push {
+    /// Checks whether the block may still be pushed in a direction.
+    pub fn can_move(&self, dir: (i32, i32)) -> bool {
+        self.directions.contains(&dir) && !(self.once && self.pushed)
+    }
+}
 impl Component for Push {}
+
+pub struct PressurePlate {
+    pub target: String,
+    pub active: bool,
+}
+impl Component for PressurePlate {}
+
+pub struct Switch {
+    pub target: String,
+    pub on: bool,
+}
+impl Component for Switch {}
+
+/// Opens while any plate or switch targeting its name is active (or closes, if inverted).
+/// Gates with tiles set also rewrite the map tile underneath them.
+pub struct Gate {
+    pub name: String,
+    pub open: bool,
+    pub inverted: bool,
+    pub open_spr: Option<i32>,
+    pub closed_spr: Option<i32>,
+    pub open_tile: Option<u8>,
+    pub closed_tile: Option<u8>,
+}
+impl Component for Gate {}
+
+pub struct Falling {
+    pub ticks: u32,
+}
+impl Component for Falling {}
+
+pub struct Solid;
+impl Component for Solid {}
+
+pub struct Pusher;
+impl Component for Pusher {}
+
+/// A non-solid area that raises events as other colliders enter, stay in and exit it.
+/// Triggers with a target also latch any gates of that name once the player walks in.
+pub struct Trigger {
+    pub target: Option<String>,
+    pub fired: bool,
+    pub inside: Vec<usize>,
+}
+impl Component for Trigger {
+    fn forget(&mut self, entity: usize) {
+        self.inside.retain(|&i| i != entity);
+    }
+}
+
+/// A shot that flies along with its `Vel` and hurts whatever it hits.
+pub struct Projectile {
+    /// The entity that fired it, which it never hits, unless it has since been despawned.
+    pub owner: Option<usize>,
+    pub damage: Damage,
+    /// Ticks left before it disappears.
+    pub lifetime: u32,
+    /// How many more targets it can pass through after the next one.
+    pub pierce: u32,
+    /// Whether it lodges in walls for a while, rather than breaking on them.
+    pub sticks: bool,
+    /// The wall tile it is lodged in, once stuck. It falls out if the tile stops being solid.
+    pub lodged: Option<(i32, i32)>,
+    /// Targets already hit, which it won't hit again.
+    pub hit: Vec<usize>,
+}
+impl Component for Projectile {
+    fn forget(&mut self, entity: usize) {
+        if self.owner == Some(entity) {
+            self.owner = None;
+        }
+        self.hit.retain(|&i| i != entity);
+    }
+}
+
+/// Makes an entity fire at the player every `interval` ticks while it can see them.
+#[derive(Clone)]
+pub struct Shooter {
+    pub interval: u32,
+    pub cooldown: u32,
+    pub speed: i32,
+    pub damage: Damage,
+    pub pattern: Pattern,
+}
+impl Component for Shooter {}
+
+/// The shots a [`Shooter`] fires each time.
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    /// A single shot straight at the player.
+    Aimed,
+    /// `count` shots fanned out across `angle` radians, centred on the player.
+    Spread { count: u32, angle: f32 },
+    /// `count` shots evenly spaced all the way around, one of them towards the player.
+    Ring { count: u32 },
+}
+
+/// Blows up once `fuse` runs out, hurting everything within `radius` of its centre.
+pub struct Bomb {
+    pub fuse: u32,
+    pub radius: i32,
+    pub damage: i32,
+}
+impl Component for Bomb {}
+
+/// The flash left by a bomb going off, centred on the entity's position.
+pub struct Explosion {
+    pub ticks: u32,
+    pub radius: i32,
+}
+impl Component for Explosion {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Item {
+    Heart,
+    /// Raises the player's maximum health by a heart, and fills it.
+    HeartContainer,
+    Coin,
+    Arrows,
+    Bombs,
+}
+
+/// One possible outcome of a loot roll, where `None` means dropping nothing.
+#[derive(Clone, Copy)]
+pub struct LootEntry {
+    pub item: Option<Item>,
+    pub amount: u32,
+    pub weight: u32,
+}
+
+/// What an entity may drop when it dies, each entry chosen in proportion to its weight.
+pub struct Loot(pub Vec<LootEntry>);
+impl Component for Loot {}
+
+/// An item lying on the floor, collected when the player touches it and gone after `ticks`.
+pub struct Pickup {
+    pub item: Item,
+    pub amount: u32,
+    pub ticks: u32,
+}
+impl Component for Pickup {}
+
+/// Plays out the player's death, after which the game is over.
+pub struct Dying {
+    pub ticks: u32,
+}
+impl Component for Dying {}
+
+/// Saves the game when the player touches it, so that they continue from here after dying.
+#[derive(Default)]
+pub struct Checkpoint {
+    pub active: bool,
+}
+impl Component for Checkpoint {}
+
+/// One stage of a boss fight, which lasts until the boss's health drops to the next one's.
+pub struct BossPhase {
+    /// The health at or below which this phase begins.
+    pub health: i32,
+    /// How fast the boss chases, if it follows anything.
+    pub speed: Option<i32>,
+    /// What the boss fires in this phase, if anything.
+    pub shooter: Option<Shooter>,
+}
+
+/// A named enemy fought in phases, which keeps the gates named `arena` shut while it lives.
+pub struct Boss {
+    pub name: String,
+    pub arena: Option<String>,
+    pub phases: Vec<BossPhase>,
+    /// The phase in progress, or `None` before the fight has started.
+    pub phase: Option<usize>,
+}
+impl Component for Boss {}
+
+/// Lets an entity slide around corners that block it by no more than `tolerance`.
+#[derive(Clone, Copy)]
+pub struct Nudge {
+    pub tolerance: i32,
+}
+impl Component for Nudge {}
@@ -10,6 +10,10 @@ type SparseVec<T> = Vec<Option<T>>;
 type ComponentMap = HashMap<TypeId, Box<dyn ComponentVec>>;
 /// A system, represented as a boxed closure.
 type System<T> = Box<dyn FnMut(&World<T>, &T)>;
+/// A map from resource types to resources, each wrapped in a [`RefCell`].
+type ResourceMap = HashMap<TypeId, Box<dyn Any>>;
+/// The components of an entity that is yet to be inserted, keyed by type.
+type EntityComponents = HashMap<TypeId, Box<dyn Any>>;
 
 /// A container to store all components and systems in use at any point.
 pub struct World<C> {
@@ -19,6 +23,16 @@ pub struct World<C> {
     entities: usize,
     /// The registered systems.
     systems: RefCell<Vec<System<C>>>,
+    /// The registered systems that only draw, which run before all others.
+    draw_systems: RefCell<Vec<System<C>>>,
+    /// Singleton values that do not belong to any entity.
+    resources: ResourceMap,
+    /// Entities spawned by systems, inserted once the current tick is over.
+    spawned: RefCell<Vec<EntityComponents>>,
+    /// Entities despawned by systems, cleared once the current tick is over.
+    despawned: RefCell<Vec<usize>>,
+    /// Indices of despawned entities, free to be reused by spawned ones.
+    free: Vec<usize>,
 }
 
 impl<C> World<C> {
@@ -28,6 +42,11 @@ impl<C> World<C> {
             components: ComponentMap::new(),
             entities: 0,
             systems: RefCell::new(Vec::new()),
+            draw_systems: RefCell::new(Vec::new()),
+            resources: ResourceMap::new(),
+            spawned: RefCell::new(Vec::new()),
+            despawned: RefCell::new(Vec::new()),
+            free: Vec::new(),
         }
     }
 
@@ -53,8 +72,33 @@ impl<C> World<C> {
         self.insert(builder.0)
     }
 
+    /// Queues a new entity to be inserted at the end of the current tick, for use by systems.
+    pub fn spawn(&self, f: impl FnOnce(&mut EntityBuilder) -> &mut EntityBuilder) {
+        let mut builder = EntityBuilder::default();
+        f(&mut builder);
+        self.spawned.borrow_mut().push(builder.0);
+    }
+
+    /// Queues an entity to have all of its components removed at the end of the current tick.
+    ///
+    /// Its index is then reused by later spawns, so nothing should keep referring to it.
+    pub fn despawn(&self, entity: usize) {
+        self.despawned.borrow_mut().push(entity);
+    }
+
+    /// Stores a spawned entity, reusing the index of a despawned one where possible.
+    fn insert_spawned(&mut self, mut components: EntityComponents) {
+        let Some(index) = self.free.pop() else {
+            self.insert(components);
+            return;
+        };
+        for (typeid, vec) in &mut self.components {
+            vec.set(index, components.remove(typeid));
+        }
+    }
+
     /// Inserts a new entity given a map of types to components.
-    fn insert(&mut self, mut components: HashMap<TypeId, Box<dyn Any>>) -> usize {
+    fn insert(&mut self, mut components: EntityComponents) -> usize {
         self.entities += 1;
         for (typeid, vec) in &mut self.components {
             vec.insert(components.remove(typeid));
@@ -82,23 +126,77 @@ impl<C> World<C> {
         self.cell().borrow_mut()
     }
 
+    /// Stores a resource in the `World`, replacing any existing resource of the same type.
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) {
+        self.resources
+            .insert(TypeId::of::<T>(), Box::new(RefCell::new(resource)));
+    }
+
+    /// Retrieves the [`std::cell::RefCell`] containing a resource.
+    fn resource_cell<T: 'static>(&self) -> &RefCell<T> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .expect("Attempted to borrow non-existent resource")
+            .downcast_ref::<RefCell<T>>()
+            .unwrap()
+    }
+
+    /// Returns an immutable reference to a resource.
+    pub fn resource<T: 'static>(&self) -> Ref<'_, T> {
+        self.resource_cell().borrow()
+    }
+
+    /// Returns a mutable reference to a resource.
+    pub fn resource_mut<T: 'static>(&self) -> RefMut<'_, T> {
+        self.resource_cell().borrow_mut()
+    }
+
     /// Adds a system to the world.
     pub fn system<T: FnMut(&World<C>, &C) + 'static>(&mut self, system: T) {
         self.systems.get_mut().push(Box::new(system))
     }
 
-    /// Calls all systems in order.
+    /// Adds a system that only draws, so that it can also run while the world is paused.
+    pub fn draw_system<T: FnMut(&World<C>, &C) + 'static>(&mut self, system: T) {
+        self.draw_systems.get_mut().push(Box::new(system))
+    }
+
+    /// Calls only the drawing systems, showing the world without advancing it.
+    pub fn draw(&self, ctx: &C) {
+        for system in self.draw_systems.borrow_mut().iter_mut() {
+            system(self, ctx);
+        }
+    }
+
+    /// Calls all systems in order, drawing ones first, then applies any spawns and despawns they
+    /// queued.
     pub fn tick(&mut self, ctx: &C) {
+        self.draw(ctx);
         let mut systems = self.systems.borrow_mut();
         for system in systems.iter_mut() {
             system(self, ctx);
         }
+        drop(systems);
+
+        let mut despawned = self.despawned.take();
+        despawned.sort_unstable();
+        despawned.dedup();
+        for entity in despawned {
+            for vec in self.components.values_mut() {
+                vec.set(entity, None);
+                vec.forget(entity);
+            }
+            self.free.push(entity);
+        }
+        for components in self.spawned.take() {
+            self.insert_spawned(components);
+        }
     }
 }
 
 /// A helper struct for constructing new entities.
 #[derive(Default)]
-pub struct EntityBuilder(HashMap<TypeId, Box<dyn Any>>);
+pub struct EntityBuilder(EntityComponents);
 
 impl EntityBuilder {
     /// Adds a component to the entity.
@@ -108,8 +206,12 @@ impl EntityBuilder {
     }
 }
 
-/// A marker trait indicating that a type may be used as component data.
-pub trait Component: 'static {}
+/// A trait indicating that a type may be used as component data.
+pub trait Component: 'static {
+    /// Drops any reference this component holds to a despawned entity, as its index may soon be
+    /// reused by a new one.
+    fn forget(&mut self, _entity: usize) {}
+}
 
 /// A trait for dynamically typed, dynamically borrow checked component vectors.
 trait ComponentVec {
@@ -119,6 +221,10 @@ trait ComponentVec {
     fn as_any_mut(&mut self) -> &mut dyn Any;
     /// Inserts a component into the vector.
     fn insert(&mut self, component: Option<Box<dyn Any>>);
+    /// Replaces the component at an index, which may remove it.
+    fn set(&mut self, index: usize, component: Option<Box<dyn Any>>);
+    /// Has every component in the vector forget a despawned entity.
+    fn forget(&mut self, entity: usize);
 }
 
 impl<T: Component> ComponentVec for RefCell<SparseVec<T>> {
@@ -134,4 +240,14 @@ impl<T: Component> ComponentVec for RefCell<SparseVec<T>> {
         self.get_mut()
             .push(component.map(|x| *x.downcast().unwrap()));
     }
+
+    fn set(&mut self, index: usize, component: Option<Box<dyn Any>>) {
+        self.get_mut()[index] = component.map(|x| *x.downcast().unwrap());
+    }
+
+    fn forget(&mut self, entity: usize) {
+        for component in self.get_mut().iter_mut().flatten() {
+            component.forget(entity);
+        }
+    }
 }
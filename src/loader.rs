@@ -1,6 +1,8 @@
 use crate::components::*;
 use crate::ecs::{EntityBuilder, World};
-use crate::Context;
+use crate::resources::{Entrance, Rng};
+use crate::tilemap::{Exits, OutOfBounds};
+use crate::{Context, SUBPIXELS};
 
 use serde_json::{Map, Value};
 
@@ -11,6 +13,122 @@ fn get_int<T: Default + TryFrom<i64>>(obj: &Map<String, Value>, key: &str) -> T
     }
 }
 
+/// Reads a number of pixels, which may be fractional, as a fixed-point value.
+fn get_fixed(obj: &Map<String, Value>, key: &str) -> i32 {
+    (obj[key].as_f64().unwrap() * SUBPIXELS as f64).round() as i32
+}
+
+fn get_layer(name: &str) -> u32 {
+    match name {
+        "world" => layer::WORLD,
+        "player" => layer::PLAYER,
+        "enemy" => layer::ENEMY,
+        "projectile" => layer::PROJECTILE,
+        "pickup" => layer::PICKUP,
+        "trigger" => layer::TRIGGER,
+        other => panic!("unrecognised collision layer {other}"),
+    }
+}
+
+fn get_direction(name: &str) -> (i32, i32) {
+    match name {
+        "up" => (0, -1),
+        "down" => (0, 1),
+        "left" => (-1, 0),
+        "right" => (1, 0),
+        other => panic!("unrecognised direction {other}"),
+    }
+}
+
+fn get_damage_kind(name: &str) -> DamageKind {
+    match name {
+        "slash" => DamageKind::Slash,
+        "fire" => DamageKind::Fire,
+        "explosive" => DamageKind::Explosive,
+        "fall" => DamageKind::Fall,
+        "poison" => DamageKind::Poison,
+        other => panic!("unrecognised damage kind {other}"),
+    }
+}
+
+/// Reads the optional kind of damage an entity deals, which defaults to slashing.
+fn get_kind(obj: &Map<String, Value>) -> DamageKind {
+    match obj.get("kind").and_then(Value::as_str) {
+        Some(name) => get_damage_kind(name),
+        None => DamageKind::Slash,
+    }
+}
+
+/// Reads the optional status effect an entity inflicts, along with how long it lasts.
+fn get_status(obj: &Map<String, Value>) -> Option<(Status, u32)> {
+    let status = match obj.get("status")?.as_str().unwrap() {
+        "poison" => Status::Poison,
+        "stun" => Status::Stun,
+        "freeze" => Status::Freeze,
+        "burn" => Status::Burn,
+        other => panic!("unrecognised status effect {other}"),
+    };
+    Some((status, get_int(obj, "status_ticks")))
+}
+
+fn get_damage_kinds(obj: &Map<String, Value>, key: &str) -> Vec<DamageKind> {
+    match obj.get(key).and_then(Value::as_array) {
+        Some(names) => names
+            .iter()
+            .map(|name| get_damage_kind(name.as_str().unwrap()))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn get_item(name: &str) -> Option<Item> {
+    match name {
+        "nothing" => None,
+        "heart" => Some(Item::Heart),
+        "heart_container" => Some(Item::HeartContainer),
+        "coin" => Some(Item::Coin),
+        "arrows" => Some(Item::Arrows),
+        "bombs" => Some(Item::Bombs),
+        other => panic!("unrecognised item {other}"),
+    }
+}
+
+fn get_str(obj: &Map<String, Value>, key: &str) -> String {
+    obj[key].as_str().unwrap().to_owned()
+}
+
+fn get_bool(obj: &Map<String, Value>, key: &str) -> bool {
+    obj.get(key).and_then(Value::as_bool).unwrap_or_default()
+}
+
+fn get_opt_int<T: TryFrom<i64>>(obj: &Map<String, Value>, key: &str) -> Option<T> {
+    obj.get(key)?.as_i64()?.try_into().ok()
+}
+
+fn get_shooter(obj: &Map<String, Value>) -> Shooter {
+    let pattern = match obj.get("pattern").and_then(Value::as_str) {
+        None | Some("aimed") => Pattern::Aimed,
+        Some("spread") => Pattern::Spread {
+            count: get_int(obj, "count"),
+            angle: (obj["angle"].as_f64().unwrap() as f32).to_radians(),
+        },
+        Some("ring") => Pattern::Ring {
+            count: get_int(obj, "count"),
+        },
+        Some(other) => panic!("unrecognised shot pattern {other}"),
+    };
+    Shooter {
+        interval: get_int(obj, "interval"),
+        cooldown: 0,
+        speed: get_fixed(obj, "speed"),
+        damage: Damage {
+            status: get_status(obj),
+            ..Damage::new(get_int(obj, "damage"), get_kind(obj))
+        },
+        pattern,
+    }
+}
+
 fn entity_loader(components: &Vec<Value>) -> impl FnOnce(&mut EntityBuilder) -> &mut EntityBuilder {
     let components: Vec<Value> = components.clone();
     move |entity| {
@@ -18,34 +136,175 @@ fn entity_loader(components: &Vec<Value>) -> impl FnOnce(&mut EntityBuilder) ->
             let obj = component.as_object().unwrap();
             match obj["type"].as_str().unwrap() {
                 "pos" => {
-                    entity.with_component(Pos::new(get_int(obj, "x"), get_int(obj, "y")));
+                    entity.with_component(Pos::new(get_fixed(obj, "x"), get_fixed(obj, "y")));
                 }
                 "vel" => {
-                    entity.with_component(Vel::new(get_int(obj, "x"), get_int(obj, "y")));
+                    entity.with_component(Vel::new(get_fixed(obj, "x"), get_fixed(obj, "y")));
                 }
                 "spr" => {
                     entity.with_component(Spr(get_int(obj, "id")));
                 }
                 "player" => {
-                    entity.with_component(Player::default());
+                    entity.with_component(Player {
+                        arrows: get_opt_int(obj, "arrows").unwrap_or_default(),
+                        bombs: get_opt_int(obj, "bombs").unwrap_or_default(),
+                        ..Default::default()
+                    });
                 }
                 "collider" => {
-                    entity.with_component(Collider::new(get_int(obj, "w"), get_int(obj, "h")));
+                    let mut collider = match obj.get("shape").and_then(Value::as_str) {
+                        None | Some("aabb") => {
+                            Collider::new(get_fixed(obj, "w"), get_fixed(obj, "h"))
+                        }
+                        Some("circle") => Collider::circle(get_fixed(obj, "r")),
+                        Some(other) => panic!("unrecognised collider shape {other}"),
+                    };
+                    if let Some(name) = obj.get("layer").and_then(Value::as_str) {
+                        collider.layer = get_layer(name);
+                    }
+                    if let Some(names) = obj.get("mask").and_then(Value::as_array) {
+                        collider.mask = names
+                            .iter()
+                            .map(|name| get_layer(name.as_str().unwrap()))
+                            .fold(0, |mask, layer| mask | layer);
+                    }
+                    entity.with_component(collider);
                 }
                 "health" => {
-                    entity.with_component(Health(get_int(obj, "val")));
+                    let max = get_int(obj, "val");
+                    entity.with_component(Health {
+                        current: get_opt_int(obj, "current").unwrap_or(max),
+                        max,
+                    });
                 }
                 "healthmod" => {
                     entity.with_component(HealthMod {
                         health: get_int(obj, "val"),
-                        cooldown: 0,
+                        kind: get_kind(obj),
+                        status: get_status(obj),
+                    });
+                }
+                "resistances" => {
+                    entity.with_component(Resistances {
+                        immune: get_damage_kinds(obj, "immune"),
+                        resist: get_damage_kinds(obj, "resist"),
+                        weak: get_damage_kinds(obj, "weak"),
+                    });
+                }
+                "iframes" => {
+                    entity.with_component(IFrames {
+                        ticks: get_int(obj, "ticks"),
                     });
                 }
                 "follow" => {
-                    entity.with_component(Follow(get_int(obj, "id")));
+                    entity.with_component(Follow {
+                        target: get_int(obj, "id"),
+                        speed: match obj.get("speed") {
+                            Some(_) => get_fixed(obj, "speed"),
+                            None => SUBPIXELS,
+                        },
+                    });
                 }
                 "push" => {
-                    entity.with_component(Push);
+                    let directions = match obj.get("directions").and_then(Value::as_array) {
+                        Some(names) => names
+                            .iter()
+                            .map(|name| get_direction(name.as_str().unwrap()))
+                            .collect(),
+                        None => vec![(0, -1), (0, 1), (-1, 0), (1, 0)],
+                    };
+                    entity.with_component(Push {
+                        once: get_bool(obj, "push_once"),
+                        pushed: false,
+                        directions,
+                        max_chain: get_opt_int(obj, "max_chain").unwrap_or(1),
+                        dir: (0, 0),
+                        progress: 0,
+                        slide: 0,
+                    });
+                }
+                "solid" => {
+                    entity.with_component(Solid);
+                }
+                "pusher" => {
+                    entity.with_component(Pusher);
+                }
+                "trigger" => {
+                    entity.with_component(Trigger {
+                        target: obj.get("target").and_then(Value::as_str).map(str::to_owned),
+                        fired: false,
+                        inside: Vec::new(),
+                    });
+                }
+                "shooter" => {
+                    entity.with_component(get_shooter(obj));
+                }
+                "bomb" => {
+                    entity.with_component(Bomb {
+                        fuse: get_int(obj, "fuse"),
+                        radius: get_fixed(obj, "radius"),
+                        damage: get_int(obj, "damage"),
+                    });
+                }
+                "loot" => {
+                    let entries = obj["table"].as_array().unwrap().iter().map(|entry| {
+                        let entry = entry.as_object().unwrap();
+                        LootEntry {
+                            item: get_item(entry["item"].as_str().unwrap()),
+                            amount: get_opt_int(entry, "amount").unwrap_or(1),
+                            weight: get_int(entry, "weight"),
+                        }
+                    });
+                    entity.with_component(Loot(entries.collect()));
+                }
+                "checkpoint" => {
+                    entity.with_component(Checkpoint::default());
+                }
+                "boss" => {
+                    let phases = obj["phases"].as_array().unwrap().iter().map(|phase| {
+                        let phase = phase.as_object().unwrap();
+                        BossPhase {
+                            health: get_int(phase, "health"),
+                            speed: phase.get("speed").map(|_| get_fixed(phase, "speed")),
+                            shooter: phase
+                                .get("shooter")
+                                .map(|shooter| get_shooter(shooter.as_object().unwrap())),
+                        }
+                    });
+                    entity.with_component(Boss {
+                        name: get_str(obj, "name"),
+                        arena: obj.get("arena").map(|_| get_str(obj, "arena")),
+                        phases: phases.collect(),
+                        phase: None,
+                    });
+                }
+                "nudge" => {
+                    entity.with_component(Nudge {
+                        tolerance: get_fixed(obj, "tolerance"),
+                    });
+                }
+                "plate" => {
+                    entity.with_component(PressurePlate {
+                        target: get_str(obj, "target"),
+                        active: false,
+                    });
+                }
+                "switch" => {
+                    entity.with_component(Switch {
+                        target: get_str(obj, "target"),
+                        on: get_bool(obj, "on"),
+                    });
+                }
+                "gate" => {
+                    entity.with_component(Gate {
+                        name: get_str(obj, "name"),
+                        open: false,
+                        inverted: get_bool(obj, "inverted"),
+                        open_spr: get_opt_int(obj, "open_spr"),
+                        closed_spr: get_opt_int(obj, "closed_spr"),
+                        open_tile: get_opt_int(obj, "open_tile"),
+                        closed_tile: get_opt_int(obj, "closed_tile"),
+                    });
                 }
                 other => panic!("unrecognised component {other}"),
             }
@@ -54,10 +313,32 @@ fn entity_loader(components: &Vec<Value>) -> impl FnOnce(&mut EntityBuilder) ->
     }
 }
 
-pub fn load_level(world: &mut World<Context>, level_str: &str) -> usize {
+/// Loads a level's entities into the world, returning the level's map index and out-of-bounds
+/// policy.
+pub fn load_level(world: &mut World<Context>, level_str: &str) -> (usize, OutOfBounds) {
     let level: Value = serde_json::from_str(level_str).unwrap();
+    if let Some(seed) = level.get("seed").and_then(Value::as_u64) {
+        world.insert_resource(Rng::new(seed));
+    }
     for entity in level["entities"].as_array().unwrap() {
         world.add_entity(entity_loader(entity.as_array().unwrap()));
     }
-    level["map"].as_u64().unwrap() as usize
+    let player = world.get::<Player>().iter().position(Option::is_some);
+    let entrance = player.and_then(|i| world.get::<Pos>()[i]);
+    world.insert_resource(Entrance(entrance));
+    let out_of_bounds = match level.get("out_of_bounds").and_then(Value::as_str) {
+        None | Some("solid") => OutOfBounds::Solid,
+        Some("passable") => OutOfBounds::Passable,
+        Some("exit") => {
+            let exits = level["exits"].as_object().unwrap();
+            OutOfBounds::Exit(Exits {
+                left: get_opt_int(exits, "left"),
+                right: get_opt_int(exits, "right"),
+                up: get_opt_int(exits, "up"),
+                down: get_opt_int(exits, "down"),
+            })
+        }
+        Some(other) => panic!("unrecognised out-of-bounds policy {other}"),
+    };
+    (level["map"].as_u64().unwrap() as usize, out_of_bounds)
 }
@@ -1,25 +1,38 @@
+mod broadphase;
 mod components;
 mod ecs;
 mod loader;
+mod raycast;
+mod resources;
+mod save;
 mod systems;
+mod tilemap;
 mod tiles;
 mod util;
 
+use std::cell::RefCell;
+
 use components::*;
 use ecs::World;
 use loader::load_level;
+use resources::{register_resources, CheckpointReached, Entrance, GameOver, RoomExit};
+use save::Save;
 use systems::*;
+use tilemap::Tilemap;
 use util::draw_tiles;
 
 use macroquad::prelude::*;
 
 const TILE_SIZE: i32 = 16;
+/// The number of fixed-point units in one pixel, used for all positions and velocities.
+const SUBPIXELS: i32 = 256;
 const LEVEL_WIDTH: usize = 16;
+const LEVEL_HEIGHT: usize = 12;
 
 /// Global data passed to all systems
 pub struct Context {
     tileset: Texture2D,
-    map: &'static [u8],
+    map: RefCell<Tilemap>,
 }
 
 const LEVELS: &[&str] = &[
@@ -32,6 +45,113 @@ const MAPS: &[&[u8]] = &[
     include_bytes!("../levels/level2.dat"),
 ];
 
+/// Builds a fresh `World` for a level.
+///
+/// The live map is saved back into `maps` first, so any changes made to it persist for the rest of
+/// the dungeon visit.
+fn enter_level(
+    level: usize,
+    context: &mut Context,
+    maps: &mut [Tilemap],
+    map_id: &mut usize,
+) -> World<Context> {
+    maps[*map_id] = context.map.get_mut().clone();
+
+    let mut world = World::new();
+    register_components(&mut world);
+    register_resources(&mut world);
+    register_systems(&mut world);
+    let (id, out_of_bounds) = load_level(&mut world, LEVELS[level]);
+    *map_id = id;
+    *context.map.get_mut() = maps[*map_id].clone();
+    context.map.get_mut().out_of_bounds = out_of_bounds;
+    world
+}
+
+/// Moves the player out of the room they left and into a newly entered one, keeping their health
+/// and belongings, and places them just inside the edge they came in by.
+fn carry_player(from: &World<Context>, to: &World<Context>, dir: (i32, i32), map: &Tilemap) {
+    let player_idx = |world: &World<Context>| {
+        world
+            .get::<Player>()
+            .iter()
+            .position(Option::is_some)
+            .unwrap()
+    };
+    let (old, new) = (player_idx(from), player_idx(to));
+
+    let mut player = from.get_mut::<Player>()[old].take().unwrap();
+    player.swing = None;
+    player.safe_pos = None;
+    to.get_mut::<Player>()[new] = Some(player);
+    to.get_mut::<Health>()[new] = from.get_mut::<Health>()[old].take();
+    to.get_mut::<StatusEffects>()[new] = from.get_mut::<StatusEffects>()[old].take();
+
+    // Arrive through the opening on the entry edge that lines up best with where the player left.
+    let left_at = from.get::<Pos>()[old].unwrap();
+    let tile = TILE_SIZE * SUBPIXELS;
+    let (width, height) = (LEVEL_WIDTH as i32, map.height());
+    let entry = |i: i32| match dir {
+        (1, 0) => (0, i),
+        (-1, 0) => (width - 1, i),
+        (0, 1) => (i, 0),
+        _ => (i, height - 1),
+    };
+    let (along, count) = if dir.0 != 0 {
+        (left_at.y, height)
+    } else {
+        (left_at.x, width)
+    };
+    let opening = (0..count)
+        .filter(|&i| {
+            let (x, y) = entry(i);
+            !map.solid_at(x, y)
+        })
+        .min_by_key(|&i| (i * tile - along).abs());
+    if let Some(i) = opening {
+        let (x, y) = entry(i);
+        let pos = Pos::new(x * tile, y * tile);
+        to.get_mut::<Pos>()[new] = Some(pos);
+        to.resource_mut::<Entrance>().0 = Some(pos);
+    }
+}
+
+/// Copies every map in the dungeon, including the changes made so far to the live one.
+fn snapshot_maps(context: &Context, maps: &[Tilemap], map_id: usize) -> Vec<Tilemap> {
+    let mut maps = maps.to_vec();
+    maps[map_id] = context.map.borrow().clone();
+    maps
+}
+
+/// Saves the game with the player where they stand, such as on first entering a level.
+fn autosave(
+    world: &World<Context>,
+    level: usize,
+    context: &Context,
+    maps: &[Tilemap],
+    map_id: usize,
+) -> Save {
+    let player_idx = world
+        .get::<Player>()
+        .iter()
+        .position(Option::is_some)
+        .unwrap();
+    let pos = world.get::<Pos>()[player_idx].unwrap();
+    Save::capture(world, level, pos, snapshot_maps(context, maps, map_id))
+}
+
+const GAME_OVER_OPTIONS: [&str; 2] = ["Continue", "Quit"];
+
+fn draw_game_over(choice: usize) {
+    draw_rectangle(0.0, 0.0, 256.0, 192.0, Color::new(0.0, 0.0, 0.0, 0.75));
+    draw_text("GAME OVER", 92.0, 80.0, 16.0, RED);
+    for (i, option) in GAME_OVER_OPTIONS.iter().enumerate() {
+        let marker = if i == choice { ">" } else { " " };
+        let y = 104.0 + i as f32 * 14.0;
+        draw_text(&format!("{marker} {option}"), 100.0, y, 14.0, WHITE);
+    }
+}
+
 fn window_conf() -> Conf {
     Conf {
         window_title: "Dungeon Oxide".into(),
@@ -44,21 +164,21 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    let mut maps: Vec<Tilemap> = MAPS.iter().map(|map| Tilemap::new(map)).collect();
+    let mut map_id = 0;
     let mut context = Context {
         tileset: Texture2D::from_file_with_format(
             include_bytes!("../assets/tiles.png"),
             Some(ImageFormat::Png),
         ),
-        map: MAPS[0],
+        map: RefCell::new(maps[map_id].clone()),
     };
 
-    let mut world = World::new();
-    register_components(&mut world);
-    register_systems(&mut world);
-
     let mut current_level = 0;
-    let map_id = load_level(&mut world, LEVELS[current_level]);
-    context.map = MAPS[map_id];
+    let mut world = enter_level(current_level, &mut context, &mut maps, &mut map_id);
+    let mut save = autosave(&world, current_level, &context, &maps, map_id);
+    // The highlighted menu option, while the game-over screen is showing.
+    let mut game_over: Option<usize> = None;
 
     let render_target = render_target(256, 192);
     render_target.texture.set_filter(FilterMode::Nearest);
@@ -69,37 +189,72 @@ async fn main() {
         ..Default::default()
     };
     loop {
+        let mut next_level = None;
+        // The direction the player walked into the next room in. Skipping there with the debug
+        // keys neither carries the player over nor saves the game.
+        let mut entered_by = None;
         if is_key_pressed(KeyCode::Comma) {
-            current_level = (current_level - 1).rem_euclid(LEVELS.len());
-            world = World::new();
-            register_components(&mut world);
-            register_systems(&mut world);
-            let map_id = load_level(&mut world, LEVELS[current_level]);
-            context.map = MAPS[map_id];
+            next_level = Some((current_level - 1).rem_euclid(LEVELS.len()));
         }
         if is_key_pressed(KeyCode::Period) {
-            current_level = (current_level + 1).rem_euclid(LEVELS.len());
-            world = World::new();
-            register_components(&mut world);
-            register_systems(&mut world);
-            let map_id = load_level(&mut world, LEVELS[current_level]);
-            context.map = MAPS[map_id];
-        }
-        let player_idx = world.get::<Player>().iter().position(Option::is_some).unwrap();
-        if world.get::<Spr>()[player_idx].is_none() {
-            world = World::new();
-            register_components(&mut world);
-            register_systems(&mut world);
-            let map_id = load_level(&mut world, LEVELS[current_level]);
-            context.map = MAPS[map_id];
+            next_level = Some((current_level + 1).rem_euclid(LEVELS.len()));
         }
 
         set_camera(&camera);
         clear_background(BLACK);
 
-        draw_tiles(context.map, context.tileset);
+        draw_tiles(context.map.borrow().tiles(), context.tileset);
 
-        world.tick(&context);
+        if let Some(choice) = game_over.as_mut() {
+            world.draw(&context);
+            draw_game_over(*choice);
+            if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+                *choice = choice.saturating_sub(1);
+            }
+            if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+                *choice = (*choice + 1).min(GAME_OVER_OPTIONS.len() - 1);
+            }
+            if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+                if GAME_OVER_OPTIONS[*choice] == "Quit" {
+                    break;
+                }
+                // Put the dungeon back as it was, so that entering the level doesn't save over it.
+                maps.clone_from(&save.maps);
+                *context.map.get_mut() = maps[map_id].clone();
+                current_level = save.level;
+                world = enter_level(current_level, &mut context, &mut maps, &mut map_id);
+                save.restore_player(&world);
+                game_over = None;
+            }
+        } else {
+            world.tick(&context);
+
+            let reached = world.resource_mut::<CheckpointReached>().0.take();
+            if let Some(pos) = reached {
+                let snapshot = snapshot_maps(&context, &maps, map_id);
+                save = Save::capture(&world, current_level, pos, snapshot);
+            }
+            if world.resource::<GameOver>().0 {
+                game_over = Some(0);
+            }
+            let exit = world.resource::<RoomExit>().0;
+            if let Some((level, dir)) = exit {
+                next_level = Some(level);
+                entered_by = Some(dir);
+            }
+        }
+
+        if let Some(level) = next_level {
+            current_level = level;
+            let left = std::mem::replace(
+                &mut world,
+                enter_level(current_level, &mut context, &mut maps, &mut map_id),
+            );
+            if let Some(dir) = entered_by {
+                carry_player(&left, &world, dir, &context.map.borrow());
+                save = autosave(&world, current_level, &context, &maps, map_id);
+            }
+        }
 
         set_default_camera();
         draw_texture_ex(
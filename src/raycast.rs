@@ -0,0 +1,293 @@
+//! Casting rays through the tilemap and against colliders, for line of sight and hit detection.
+
+use crate::components::{Collider, Pos, Shape};
+use crate::tilemap::Tilemap;
+use crate::{SUBPIXELS, TILE_SIZE};
+
+const TILE: i32 = TILE_SIZE * SUBPIXELS;
+
+/// The first solid tile struck by a ray.
+#[derive(Clone, Copy)]
+pub struct TileHit {
+    /// The grid position of the tile.
+    pub tile: (i32, i32),
+    /// How far along the ray the tile was struck, in fixed-point units.
+    pub distance: i32,
+}
+
+/// The first collider struck by a ray.
+#[derive(Clone, Copy)]
+pub struct EntityHit {
+    pub entity: usize,
+    /// How far along the ray the collider was struck, in fixed-point units.
+    pub distance: i32,
+}
+
+/// Returns the unit direction and length of the ray from `from` to `to`.
+fn direction(from: Pos, to: Pos) -> ((f64, f64), f64) {
+    let (dx, dy) = ((to.x - from.x) as f64, (to.y - from.y) as f64);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        ((0.0, 0.0), 0.0)
+    } else {
+        ((dx / length, dy / length), length)
+    }
+}
+
+/// Walks the tile grid from `from` towards `to`, one tile boundary at a time, and returns the
+/// first solid tile on the way. A ray starting inside a solid tile hits it straight away.
+pub fn raycast_tiles(map: &Tilemap, from: Pos, to: Pos) -> Option<TileHit> {
+    let ((ux, uy), length) = direction(from, to);
+    let mut cell = (from.x.div_euclid(TILE), from.y.div_euclid(TILE));
+    let step = (ux.signum() as i32, uy.signum() as i32);
+
+    // The distance along the ray needed to cross a whole tile, and to reach the first boundary.
+    let across = |u: f64| {
+        if u == 0.0 {
+            f64::INFINITY
+        } else {
+            TILE as f64 / u.abs()
+        }
+    };
+    let first = |start: i32, cell: i32, u: f64| {
+        if u > 0.0 {
+            ((cell + 1) * TILE - start) as f64 / u
+        } else if u < 0.0 {
+            (start - cell * TILE) as f64 / -u
+        } else {
+            f64::INFINITY
+        }
+    };
+    let (across_x, across_y) = (across(ux), across(uy));
+    let (mut next_x, mut next_y) = (first(from.x, cell.0, ux), first(from.y, cell.1, uy));
+
+    let mut distance = 0.0;
+    loop {
+        if map.solid_at(cell.0, cell.1) {
+            return Some(TileHit {
+                tile: cell,
+                distance: distance as i32,
+            });
+        }
+        if next_x < next_y {
+            distance = next_x;
+            next_x += across_x;
+            cell.0 += step.0;
+        } else {
+            distance = next_y;
+            next_y += across_y;
+            cell.1 += step.1;
+        }
+        if distance > length {
+            return None;
+        }
+    }
+}
+
+/// Finds the nearest collider, among the entities picked out by `include`, that the ray from
+/// `from` to `to` passes through.
+pub fn raycast_entities(
+    from: Pos,
+    to: Pos,
+    pos: &[Option<Pos>],
+    colliders: &[Option<Collider>],
+    include: impl Fn(usize) -> bool,
+) -> Option<EntityHit> {
+    let (dir, length) = direction(from, to);
+    pos.iter()
+        .zip(colliders)
+        .enumerate()
+        .filter(|&(i, _)| include(i))
+        .filter_map(|(i, data)| match data {
+            (Some(pos), Some(coll)) => {
+                let distance = ray_distance(from, dir, *pos, *coll)?;
+                (distance <= length).then_some((i, distance))
+            }
+            _ => None,
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, distance)| EntityHit {
+            entity,
+            distance: distance as i32,
+        })
+}
+
+/// Returns how far along a ray its first contact with a collider lies, or `None` if it never
+/// touches it. Colliders containing the origin are hit at distance zero.
+fn ray_distance(from: Pos, (ux, uy): (f64, f64), pos: Pos, collider: Collider) -> Option<f64> {
+    let (ox, oy) = (from.x as f64, from.y as f64);
+    match collider.shape {
+        Shape::Aabb => {
+            // Clip the ray against the box one axis at a time.
+            let slab = |origin: f64, u: f64, min: i32, size: i32| {
+                let (min, max) = (min as f64, (min + size) as f64);
+                if u == 0.0 {
+                    (min..=max)
+                        .contains(&origin)
+                        .then_some((f64::NEG_INFINITY, f64::INFINITY))
+                } else {
+                    let (t1, t2) = ((min - origin) / u, (max - origin) / u);
+                    Some((t1.min(t2), t1.max(t2)))
+                }
+            };
+            let (near_x, far_x) = slab(ox, ux, pos.x, collider.w)?;
+            let (near_y, far_y) = slab(oy, uy, pos.y, collider.h)?;
+            let (near, far) = (near_x.max(near_y), far_x.min(far_y));
+            (near <= far && far >= 0.0).then_some(near.max(0.0))
+        }
+        Shape::Circle => {
+            let radius = collider.w as f64 / 2.0;
+            let (cx, cy) = (pos.x as f64 + radius, pos.y as f64 + radius);
+            let (dx, dy) = (ox - cx, oy - cy);
+            let c = dx * dx + dy * dy - radius * radius;
+            if c <= 0.0 {
+                return Some(0.0);
+            }
+            let b = dx * ux + dy * uy;
+            let discriminant = b * b - c;
+            (b < 0.0 && discriminant >= 0.0).then(|| -b - discriminant.sqrt())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{Exits, OutOfBounds};
+    use crate::{LEVEL_HEIGHT, LEVEL_WIDTH};
+
+    const PX: i32 = SUBPIXELS;
+    const FLOOR: u8 = 44;
+    const WALL: u8 = 19;
+
+    fn open_map() -> Tilemap {
+        let mut map = Tilemap::new(&[FLOOR; LEVEL_WIDTH * LEVEL_HEIGHT]);
+        map.out_of_bounds = OutOfBounds::Passable;
+        map
+    }
+
+    /// The middle of a tile, in fixed-point units.
+    fn middle(x: i32, y: i32) -> Pos {
+        Pos::new(x * TILE + TILE / 2, y * TILE + TILE / 2)
+    }
+
+    #[test]
+    fn ray_reports_the_first_wall_and_its_tile() {
+        let mut map = open_map();
+        map.set(4, 2, WALL);
+        map.set(6, 2, WALL);
+        let hit = raycast_tiles(&map, middle(1, 2), middle(8, 2)).unwrap();
+        assert_eq!(hit.tile, (4, 2));
+        assert_eq!(hit.distance, 4 * TILE - middle(1, 2).x);
+    }
+
+    #[test]
+    fn ray_stopping_short_of_a_wall_misses_it() {
+        let mut map = open_map();
+        map.set(4, 2, WALL);
+        assert!(
+            raycast_tiles(&map, middle(1, 2), Pos::new(4 * TILE - 1, middle(1, 2).y)).is_none()
+        );
+    }
+
+    #[test]
+    fn ray_starting_inside_a_wall_hits_it_at_once() {
+        let mut map = open_map();
+        map.set(3, 3, WALL);
+        let hit = raycast_tiles(&map, middle(3, 3), middle(7, 3)).unwrap();
+        assert_eq!(hit.tile, (3, 3));
+        assert_eq!(hit.distance, 0);
+    }
+
+    #[test]
+    fn ray_with_no_length_only_checks_its_own_tile() {
+        let mut map = open_map();
+        map.set(3, 3, WALL);
+        assert!(raycast_tiles(&map, middle(2, 3), middle(2, 3)).is_none());
+        let hit = raycast_tiles(&map, middle(3, 3), middle(3, 3)).unwrap();
+        assert_eq!((hit.tile, hit.distance), ((3, 3), 0));
+    }
+
+    #[test]
+    fn ray_along_a_grid_line_belongs_to_the_tiles_below_it() {
+        // Tile boundaries belong to the tile that starts there, so a ray along y = TILE runs
+        // through row 1 and passes under walls in row 0.
+        let mut map = open_map();
+        map.set(4, 0, WALL);
+        let (from, to) = (Pos::new(0, TILE), Pos::new(8 * TILE, TILE));
+        assert!(raycast_tiles(&map, from, to).is_none());
+
+        map.set(5, 1, WALL);
+        let hit = raycast_tiles(&map, from, to).unwrap();
+        assert_eq!((hit.tile, hit.distance), ((5, 1), 5 * TILE));
+    }
+
+    #[test]
+    fn ray_leaving_the_map_follows_the_out_of_bounds_policy() {
+        let mut map = open_map();
+        let (from, to) = (middle(0, 2), Pos::new(-2 * TILE, middle(0, 2).y));
+        let edge = TILE / 2;
+
+        map.out_of_bounds = OutOfBounds::Solid;
+        let hit = raycast_tiles(&map, from, to).unwrap();
+        assert_eq!((hit.tile, hit.distance), ((-1, 2), edge));
+
+        map.out_of_bounds = OutOfBounds::Passable;
+        assert!(raycast_tiles(&map, from, to).is_none());
+
+        map.out_of_bounds = OutOfBounds::Exit(Exits {
+            left: Some(1),
+            ..Exits::default()
+        });
+        assert!(raycast_tiles(&map, from, to).is_none());
+
+        // Edges without a room beyond are walls.
+        map.out_of_bounds = OutOfBounds::Exit(Exits {
+            right: Some(1),
+            ..Exits::default()
+        });
+        let hit = raycast_tiles(&map, from, to).unwrap();
+        assert_eq!((hit.tile, hit.distance), ((-1, 2), edge));
+    }
+
+    #[test]
+    fn ray_meets_a_box_at_its_near_face() {
+        let coll = Collider::new(10 * PX, 10 * PX);
+        let distance = ray_distance(Pos::new(0, 5 * PX), (1.0, 0.0), Pos::new(20 * PX, 0), coll);
+        assert_eq!(distance, Some(20.0 * PX as f64));
+        let behind = ray_distance(Pos::new(0, 5 * PX), (-1.0, 0.0), Pos::new(20 * PX, 0), coll);
+        assert_eq!(behind, None);
+    }
+
+    #[test]
+    fn ray_grazing_a_circle_touches_it_once() {
+        let coll = Collider::circle(5 * PX);
+        let pos = Pos::new(20 * PX, 0);
+        // The circle's centre is at (25, 5) px with a radius of 5 px.
+        let head_on = ray_distance(Pos::new(0, 5 * PX), (1.0, 0.0), pos, coll);
+        assert_eq!(head_on, Some(20.0 * PX as f64));
+        let tangent = ray_distance(Pos::new(0, 0), (1.0, 0.0), pos, coll);
+        assert_eq!(tangent, Some(25.0 * PX as f64));
+        let miss = ray_distance(Pos::new(0, -1), (1.0, 0.0), pos, coll);
+        assert_eq!(miss, None);
+        let inside = ray_distance(Pos::new(25 * PX, 5 * PX), (1.0, 0.0), pos, coll);
+        assert_eq!(inside, Some(0.0));
+    }
+
+    #[test]
+    fn entity_ray_picks_the_nearest_included_collider() {
+        let coll = Collider::new(8 * PX, 8 * PX);
+        let pos = [
+            Some(Pos::new(40 * PX, 0)),
+            Some(Pos::new(20 * PX, 0)),
+            Some(Pos::new(10 * PX, 0)),
+        ];
+        let colliders = [Some(coll); 3];
+        let (from, to) = (Pos::new(0, 4 * PX), Pos::new(60 * PX, 4 * PX));
+        let hit = raycast_entities(from, to, &pos, &colliders, |i| i != 2).unwrap();
+        assert_eq!((hit.entity, hit.distance), (1, 20 * PX));
+
+        let short = Pos::new(15 * PX, 4 * PX);
+        assert!(raycast_entities(from, short, &pos, &colliders, |i| i != 2).is_none());
+    }
+}
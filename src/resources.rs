@@ -0,0 +1,75 @@
+use crate::components::Pos;
+use crate::ecs::World;
+use crate::Context;
+
+pub fn register_resources(world: &mut World<Context>) {
+    world.insert_resource(TriggerEvents::default());
+    world.insert_resource(RoomExit::default());
+    world.insert_resource(Rng::new(DEFAULT_SEED));
+    world.insert_resource(CheckpointReached::default());
+    world.insert_resource(GameOver::default());
+    world.insert_resource(Entrance::default());
+}
+
+/// The seed used for levels that don't choose their own.
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TriggerPhase {
+    Enter,
+    Stay,
+    Exit,
+}
+
+#[derive(Clone, Copy)]
+pub struct TriggerEvent {
+    pub phase: TriggerPhase,
+    pub trigger: usize,
+    pub other: usize,
+}
+
+/// The trigger events raised during the current tick.
+#[derive(Default)]
+pub struct TriggerEvents(pub Vec<TriggerEvent>);
+
+/// The level the player is heading to, and the direction they left in, if they walked off an
+/// exit edge this tick.
+#[derive(Default)]
+pub struct RoomExit(pub Option<(usize, (i32, i32))>);
+
+/// The respawn point of a checkpoint the player activated this tick, if any.
+#[derive(Default)]
+pub struct CheckpointReached(pub Option<Pos>);
+
+/// Where the player entered the room, for putting them back if they fall before finding safe
+/// ground.
+#[derive(Default)]
+pub struct Entrance(pub Option<Pos>);
+
+/// Set once the player's death has played out.
+#[derive(Default)]
+pub struct GameOver(pub bool);
+
+/// A small xorshift generator, seeded per level so that random outcomes can be reproduced.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero, so avoid starting there.
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a number in `0..bound`.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        (self.next_u64() % bound as u64) as u32
+    }
+}
@@ -0,0 +1,51 @@
+//! Snapshots of the game taken at checkpoints, which the player continues from after dying.
+
+use crate::components::{Health, Player, Pos};
+use crate::ecs::World;
+use crate::tilemap::Tilemap;
+use crate::Context;
+
+/// Everything needed to put the player back where they last saved.
+pub struct Save {
+    pub level: usize,
+    /// Where the player reappears.
+    pub pos: Pos,
+    /// Every map in the dungeon, as it was when the game was saved.
+    pub maps: Vec<Tilemap>,
+    max_health: Option<i32>,
+    coins: u32,
+    arrows: u32,
+    bombs: u32,
+}
+
+impl Save {
+    /// Records the player in `world`, to respawn at `pos` in `level`.
+    pub fn capture(world: &World<Context>, level: usize, pos: Pos, maps: Vec<Tilemap>) -> Self {
+        let player = world.get::<Player>();
+        let idx = player.iter().position(Option::is_some).unwrap();
+        let state = player[idx].as_ref().unwrap();
+        Self {
+            level,
+            pos,
+            maps,
+            max_health: world.get::<Health>()[idx].as_ref().map(|health| health.max),
+            coins: state.coins,
+            arrows: state.arrows,
+            bombs: state.bombs,
+        }
+    }
+
+    /// Puts the saved player into a freshly loaded `world`, back at full health.
+    pub fn restore_player(&self, world: &World<Context>) {
+        let mut player = world.get_mut::<Player>();
+        let idx = player.iter().position(Option::is_some).unwrap();
+        let state = player[idx].as_mut().unwrap();
+        state.coins = self.coins;
+        state.arrows = self.arrows;
+        state.bombs = self.bombs;
+        world.get_mut::<Pos>()[idx] = Some(self.pos);
+        if let Some(max) = self.max_health {
+            world.get_mut::<Health>()[idx] = Some(Health { current: max, max });
+        }
+    }
+}
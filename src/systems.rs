@@ -7,14 +7,40 @@ pub use graphics::*;
 use crate::{ecs::World, Context};
 
 pub fn register_systems(world: &mut World<Context>) {
-    world.system(draw_sprites);
-    world.system(draw_attack);
-    world.system(draw_health);
+    world.draw_system(draw_sprites);
+    world.draw_system(draw_attack);
+    world.draw_system(draw_projectiles);
+    world.draw_system(draw_bombs);
+    world.draw_system(draw_pickups);
+    world.draw_system(draw_fires);
+    world.draw_system(draw_checkpoints);
+    world.draw_system(draw_health);
+    world.draw_system(draw_boss_health);
+    world.draw_system(draw_inventory);
     world.system(player_input);
+    world.system(update_bosses);
+    world.system(fire_shooters);
     world.system(move_followers);
-    world.system(apply_velocities);
+    world.system(apply_conveyors);
+    world.system(update_status_effects);
+    // Pushes read the pusher's velocity, so they must run before collisions zero it.
     world.system(move_pushables);
+    world.system(apply_velocities);
+    world.system(move_projectiles);
+    world.system(update_bombs);
+    world.system(update_explosions);
+    world.system(update_fires);
+    world.system(leave_room);
+    world.system(update_plates);
+    world.system(touch_checkpoints);
+    world.system(update_triggers);
+    world.system(fire_triggers);
+    world.system(update_gates);
+    world.system(fall_into_pits);
     world.system(update_health);
+    world.system(update_invulnerability);
     world.system(decelerate);
     world.system(remove_dead);
+    world.system(update_dying);
+    world.system(collect_pickups);
 }
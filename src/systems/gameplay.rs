@@ -1,132 +1,1024 @@
+use std::cell::{Ref, RefMut};
 use std::cmp;
+use std::f32::consts::TAU;
 
+use crate::broadphase::Broadphase;
 use crate::ecs::World;
-use crate::util::aabb;
+use crate::raycast::{raycast_entities, raycast_tiles};
+use crate::resources::{
+    CheckpointReached, Entrance, GameOver, Rng, RoomExit, TriggerEvent, TriggerEvents, TriggerPhase,
+};
+use crate::tilemap::Tilemap;
+use crate::tiles::Tile;
+use crate::util::{overlaps, touching};
 use crate::{components::*, tiles};
-use crate::{Context, LEVEL_WIDTH, TILE_SIZE};
+use crate::{Context, SUBPIXELS, TILE_SIZE};
 
 use itertools::izip;
 use macroquad::prelude::*;
 
-const PLAYER_SPEED: i32 = 2;
+const PLAYER_SPEED: i32 = 2 * SUBPIXELS;
+const SWING_KNOCKBACK: i32 = 4 * SUBPIXELS;
+const ARROW_SPEED: i32 = 4 * SUBPIXELS;
+const ARROW_LIFETIME: u32 = 60;
+const SHOT_LIFETIME: u32 = 120;
+const BOMB_FUSE: u32 = 90;
+const BOMB_RADIUS: i32 = 24 * SUBPIXELS;
+const BOMB_DAMAGE: i32 = 4;
+const BOMB_SIZE: i32 = 10 * SUBPIXELS;
+const BOMB_BURN_TICKS: u32 = 60;
+const EXPLOSION_KNOCKBACK: i32 = 4 * SUBPIXELS;
+pub const EXPLOSION_TICKS: u32 = 12;
+/// The size of a tile in fixed-point units.
+const TILE: i32 = TILE_SIZE * SUBPIXELS;
+
 pub fn player_input(world: &World<Context>, _ctx: &Context) {
     let pos = world.get::<Pos>();
-    let mut health = world.get_mut::<Health>();
+    let colliders = world.get::<Collider>();
+    let mut victims = Victims::borrow(world);
     let mut vel = world.get_mut::<Vel>();
     let mut player = world.get_mut::<Player>();
+    let mut switches = world.get_mut::<Switch>();
 
-    let player_pos = pos[player.iter().position(Option::is_some).unwrap()].unwrap();
+    let player_idx = player.iter().position(Option::is_some).unwrap();
+    if world.get::<Dying>()[player_idx].is_some() || world.get::<Falling>()[player_idx].is_some() {
+        return;
+    }
+    if incapacitated(&victims.effects, player_idx) {
+        player[player_idx].as_mut().unwrap().swing = None;
+        return;
+    }
 
     for data in izip!(vel.iter_mut(), player.iter_mut()) {
         if let (Some(vel), Some(player)) = data {
             if is_key_down(KeyCode::Up) || is_key_down(KeyCode::W) {
                 vel.y = vel.y.min(-PLAYER_SPEED);
+                player.facing = (0, -1);
             }
             if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
                 vel.y = vel.y.max(PLAYER_SPEED);
+                player.facing = (0, 1);
             }
             if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
                 vel.x = vel.x.min(-PLAYER_SPEED);
+                player.facing = (-1, 0);
             }
             if is_key_down(KeyCode::Right) || is_key_down(KeyCode::D) {
                 vel.x = vel.y.max(PLAYER_SPEED);
+                player.facing = (1, 0);
             }
 
-            if is_key_down(KeyCode::Space) {
-                player.attack = true;
-                for data in izip!(pos.iter(), health.iter_mut()) {
-                    if let (Some(pos), Some(health)) = data {
-                        let dx = player_pos.x - pos.x;
-                        let dy = player_pos.y - pos.y;
-                        let distance_sq = (dx as f64).powi(2) + (dy as f64).powi(2);
-                        if distance_sq > 0.0 && distance_sq < 400.0 {
-                            health.0 -= 1;
-                        }
-                    }
+            if is_key_pressed(KeyCode::Space) && player.swing.is_none() {
+                player.swing = Some(Swing::default());
+            }
+        }
+    }
+
+    let player = player[player_idx].as_mut().unwrap();
+    if is_key_pressed(KeyCode::X) && player.arrows > 0 {
+        player.arrows -= 1;
+        let (fx, fy) = player.facing;
+        let arrow = Projectile {
+            owner: Some(player_idx),
+            damage: Damage::new(2, DamageKind::Slash),
+            lifetime: ARROW_LIFETIME,
+            pierce: 0,
+            sticks: true,
+            lodged: None,
+            hit: Vec::new(),
+        };
+        let origin = centre(pos[player_idx].unwrap(), colliders[player_idx]);
+        let vel = Vel::new(fx * ARROW_SPEED, fy * ARROW_SPEED);
+        spawn_projectile(world, origin, vel, layer::WORLD | layer::ENEMY, arrow);
+    }
+
+    if is_key_pressed(KeyCode::C) && player.bombs > 0 {
+        player.bombs -= 1;
+        let origin = centre(pos[player_idx].unwrap(), colliders[player_idx]);
+        spawn_bomb(world, origin);
+    }
+
+    let Some(swing) = player.swing.as_mut() else {
+        return;
+    };
+    swing.ticks += 1;
+    if swing.finished() {
+        player.swing = None;
+        return;
+    }
+
+    let player_pos = pos[player_idx].unwrap();
+    let player_coll = colliders[player_idx].unwrap_or(Collider::new(0, 0));
+    let Some((hitbox_pos, hitbox)) = player.hitbox(player_pos, player_coll) else {
+        return;
+    };
+    let swing = player.swing.as_mut().unwrap();
+    let struck: Vec<usize> = izip!(pos.iter(), colliders.iter())
+        .enumerate()
+        .filter(|&(i, _)| i != player_idx && !swing.hit.contains(&i))
+        .filter_map(|(i, data)| match data {
+            (Some(pos), Some(coll)) if overlaps(hitbox_pos, hitbox, *pos, *coll) => Some(i),
+            _ => None,
+        })
+        .collect();
+
+    let centre = |pos: Pos, coll: Collider| {
+        Vec2::new((pos.x + coll.w / 2) as f32, (pos.y + coll.h / 2) as f32)
+    };
+    for i in struck {
+        swing.hit.push(i);
+        if let Some(switch) = switches[i].as_mut() {
+            switch.on = !switch.on;
+        }
+        if victims.hurt(i, Damage::new(2, DamageKind::Slash)) {
+            if let Some(vel) = vel[i].as_mut() {
+                let offset = centre(pos[i].unwrap(), colliders[i].unwrap())
+                    - centre(player_pos, player_coll);
+                let away = offset.normalize_or_zero() * SWING_KNOCKBACK as f32;
+                vel.x = away.x as i32;
+                vel.y = away.y as i32;
+            }
+        }
+    }
+}
+
+/// Returns the centre of an entity.
+fn centre(pos: Pos, collider: Option<Collider>) -> Pos {
+    let Collider { w, h, .. } = collider.unwrap_or(Collider::new(0, 0));
+    Pos::new(pos.x + w / 2, pos.y + h / 2)
+}
+
+/// Returns the grid position of the tile under the centre of an entity.
+fn tile_under(pos: Pos, collider: Option<Collider>) -> (i32, i32) {
+    let Collider { w, h, .. } = collider.unwrap_or(Collider::new(0, 0));
+    (
+        (pos.x + w / 2).div_euclid(TILE),
+        (pos.y + h / 2).div_euclid(TILE),
+    )
+}
+
+/// Returns the movement properties of the floor at a grid position.
+///
+/// Anywhere outside the map behaves like ordinary floor.
+fn floor_at(map: &Tilemap, x: i32, y: i32) -> tiles::Floor {
+    map.tile_at(x, y).unwrap_or(Tile(0)).floor()
+}
+
+/// Builds a broadphase grid of the colliders of every entity matching `include`.
+fn build_grid(
+    pos: &[Option<Pos>],
+    colliders: &[Option<Collider>],
+    include: impl Fn(usize) -> bool,
+) -> Broadphase {
+    let mut grid = Broadphase::new(TILE);
+    for (i, data) in izip!(pos.iter(), colliders.iter()).enumerate() {
+        if let (Some(pos), Some(collider)) = data {
+            if include(i) {
+                grid.insert(i, *pos, *collider);
+            }
+        }
+    }
+    grid
+}
+
+/// Builds a broadphase grid of everything that blocks movement: solid entities and closed gates.
+fn blocker_grid(
+    pos: &[Option<Pos>],
+    colliders: &[Option<Collider>],
+    solid: &[Option<Solid>],
+    gates: &[Option<Gate>],
+) -> Broadphase {
+    build_grid(pos, colliders, |i| is_blocker(i, solid, gates))
+}
+
+fn is_blocker(entity: usize, solid: &[Option<Solid>], gates: &[Option<Gate>]) -> bool {
+    solid[entity].is_some() || matches!(gates[entity], Some(Gate { open: false, .. }))
+}
+
+/// Returns the bounds of the blockers that a collider might hit while moving by `delta`,
+/// excluding the moving entity itself.
+fn nearby_blockers(
+    grid: &Broadphase,
+    entity: usize,
+    from: Pos,
+    delta: Vel,
+    collider: Collider,
+    pos: &[Option<Pos>],
+    colliders: &[Option<Collider>],
+) -> Vec<(Pos, Collider)> {
+    let swept_pos = Pos::new(from.x + delta.x.min(0), from.y + delta.y.min(0));
+    let swept = Collider::new(collider.w + delta.x.abs(), collider.h + delta.y.abs());
+    grid.query(swept_pos, swept)
+        .into_iter()
+        .filter(|&other| other != entity)
+        .filter_map(|other| Some((pos[other]?, colliders[other]?)))
+        .filter(|(_, other)| collider.collides_with(other))
+        .collect()
+}
+
+/// Sweeps an entity along each axis in turn, keeping its entry in the broadphase grid up to date.
+///
+/// Returns the new position, and whether movement was cut short along each axis.
+fn move_entity(
+    entity: usize,
+    delta: Vel,
+    pos: &mut [Option<Pos>],
+    colliders: &[Option<Collider>],
+    map: &Tilemap,
+    grid: &mut Broadphase,
+    in_grid: bool,
+) -> (bool, bool) {
+    let (from, collider) = match (pos[entity], colliders[entity]) {
+        (Some(pos), Some(collider)) => (pos, collider),
+        _ => return (false, false),
+    };
+
+    let step = Vel::new(delta.x, 0);
+    let blockers = nearby_blockers(grid, entity, from, step, collider, pos, colliders);
+    let (to, hit_x) = sweep(from, step, collider, map, &blockers);
+    let step = Vel::new(0, delta.y);
+    let blockers = nearby_blockers(grid, entity, to, step, collider, pos, colliders);
+    let (to, hit_y) = sweep(to, step, collider, map, &blockers);
+
+    if in_grid {
+        grid.remove(entity, from, collider);
+        grid.insert(entity, to, collider);
+    }
+    pos[entity] = Some(to);
+    (hit_x.is_some(), hit_y.is_some())
+}
+
+/// Moves a collider along a single axis as far as it can go towards `from + delta`, stopping flush
+/// against the first solid tile or blocker in its path.
+///
+/// Returns the new position, and the normal of the surface that was hit, if any. Blockers that
+/// already overlap the collider are ignored, so that nothing gets trapped inside a gate that closes
+/// on top of it.
+fn sweep(
+    from: Pos,
+    delta: Vel,
+    collider: Collider,
+    map: &Tilemap,
+    blockers: &[(Pos, Collider)],
+) -> (Pos, Option<(i32, i32)>) {
+    let round = |coll: &Collider| coll.shape != Shape::Aabb;
+    if round(&collider) || blockers.iter().any(|(_, coll)| round(coll)) {
+        return sweep_stepped(from, delta, collider, map, blockers);
+    }
+
+    // Work in (along, across) coordinates, so that both axes can share the same logic.
+    let horizontal = delta.y == 0;
+    let flip = |a: i32, b: i32| if horizontal { (a, b) } else { (b, a) };
+    let (start, across) = flip(from.x, from.y);
+    let (size, across_size) = flip(collider.w, collider.h);
+    let (dist, _) = flip(delta.x, delta.y);
+
+    let rows = across.div_euclid(TILE)..=(across + across_size).div_euclid(TILE);
+    let hits_tiles = collider.mask & layer::WORLD != 0;
+    let solid = |col: i32| {
+        hits_tiles
+            && rows.clone().any(|row| {
+                let (x, y) = flip(col, row);
+                map.solid_at(x, y)
+            })
+    };
+
+    let mut end = start + dist;
+    if dist > 0 {
+        let lead = start + size;
+        let first = lead.div_euclid(TILE) + 1;
+        let last = (lead + dist).div_euclid(TILE);
+        if let Some(col) = (first..=last).find(|&col| solid(col)) {
+            end = col * TILE - 1 - size;
+        }
+    } else if dist < 0 {
+        let first = start.div_euclid(TILE) - 1;
+        let last = (start + dist).div_euclid(TILE);
+        if let Some(col) = (last..=first).rev().find(|&col| solid(col)) {
+            end = (col + 1) * TILE;
+        }
+    }
+
+    for &(pos, coll) in blockers {
+        let (other_start, other_across) = flip(pos.x, pos.y);
+        let (other_size, other_across_size) = flip(coll.w, coll.h);
+        if other_across > across + across_size || other_across + other_across_size < across {
+            continue;
+        }
+        if dist > 0 && other_start > start + size {
+            end = end.min(other_start - 1 - size);
+        } else if dist < 0 && other_start + other_size < start {
+            end = end.max(other_start + other_size + 1);
+        }
+    }
+
+    let (x, y) = flip(end, across);
+    let normal = (end != start + dist).then(|| flip(-dist.signum(), 0));
+    (Pos::new(x, y), normal)
+}
+
+/// Sweeps colliders of any shape, for when the exact edges of boxes can't be used, by stepping a
+/// pixel at a time and then narrowing down the point of contact within the step that hit.
+fn sweep_stepped(
+    from: Pos,
+    delta: Vel,
+    collider: Collider,
+    map: &Tilemap,
+    blockers: &[(Pos, Collider)],
+) -> (Pos, Option<(i32, i32)>) {
+    let dist = delta.x.abs() + delta.y.abs();
+    let at = |moved: i32| {
+        Pos::new(
+            from.x + moved * delta.x.signum(),
+            from.y + moved * delta.y.signum(),
+        )
+    };
+
+    // Like blockers, tiles that already overlap the collider never stop it.
+    let start_tiles = solid_tiles_overlapping(from, collider, map);
+    let start_blockers: Vec<bool> = blockers
+        .iter()
+        .map(|&(pos, coll)| overlaps(from, collider, pos, coll))
+        .collect();
+    let blocked = |moved: i32| {
+        let to = at(moved);
+        solid_tiles_overlapping(to, collider, map)
+            .iter()
+            .any(|tile| !start_tiles.contains(tile))
+            || blockers
+                .iter()
+                .zip(&start_blockers)
+                .any(|(&(pos, coll), &start)| !start && overlaps(to, collider, pos, coll))
+    };
+
+    let mut moved = 0;
+    while moved < dist {
+        let next = (moved + SUBPIXELS).min(dist);
+        if blocked(next) {
+            // `moved` is known to be free and `next` blocked, so bisect between them.
+            let mut free = moved;
+            let mut hit = next;
+            while hit - free > 1 {
+                let mid = (free + hit) / 2;
+                if blocked(mid) {
+                    hit = mid;
+                } else {
+                    free = mid;
                 }
-            } else {
-                player.attack = false;
             }
+            let normal = (-delta.x.signum(), -delta.y.signum());
+            return (at(free), Some(normal));
         }
+        moved = next;
+    }
+    (at(dist), None)
+}
+
+/// Lists the solid tiles a collider overlaps, taking its shape into account.
+fn solid_tiles_overlapping(pos: Pos, collider: Collider, map: &Tilemap) -> Vec<(i32, i32)> {
+    if collider.mask & layer::WORLD == 0 {
+        return Vec::new();
     }
+    let tile = Collider::new(TILE - 1, TILE - 1);
+    let cols = pos.x.div_euclid(TILE)..=(pos.x + collider.w).div_euclid(TILE);
+    let rows = pos.y.div_euclid(TILE)..=(pos.y + collider.h).div_euclid(TILE);
+    cols.flat_map(|x| rows.clone().map(move |y| (x, y)))
+        .filter(|&(x, y)| map.solid_at(x, y))
+        .filter(|&(x, y)| overlaps(pos, collider, Pos::new(x * TILE, y * TILE), tile))
+        .collect()
 }
 
-fn collide(pos: (i32, i32), collider: Collider, level: &[u8]) -> bool {
-    let (x1, y1) = pos;
-    let (x2, y2) = (x1 + collider.w, y1 + collider.h);
-    let tiles = [
-        (x1 / TILE_SIZE, y1 / TILE_SIZE),
-        (x2 / TILE_SIZE, y1 / TILE_SIZE),
-        (x1 / TILE_SIZE, y2 / TILE_SIZE),
-        (x2 / TILE_SIZE, y2 / TILE_SIZE),
-    ];
-    for (x, y) in tiles {
-        let idx = y as usize * LEVEL_WIDTH + x as usize;
-        if tiles::SOLID[level[idx] as usize] {
-            return true;
+/// Looks for a sideways shift, no larger than `tolerance`, that would let a collider blocked while
+/// moving by `delta` slip past the corner in its way.
+fn corner_nudge(
+    from: Pos,
+    delta: Vel,
+    collider: Collider,
+    tolerance: i32,
+    map: &Tilemap,
+    blockers: &[(Pos, Collider)],
+) -> Option<Vel> {
+    for offset in (SUBPIXELS..=tolerance).step_by(SUBPIXELS as usize) {
+        for sign in [-1, 1] {
+            let shift = if delta.y == 0 {
+                Vel::new(0, sign * offset)
+            } else {
+                Vel::new(sign * offset, 0)
+            };
+            let (shifted, hit) = sweep(from, shift, collider, map, blockers);
+            if hit.is_none() && sweep(shifted, delta, collider, map, blockers).1.is_none() {
+                return Some(shift);
+            }
         }
     }
-    false
+    None
 }
 
 pub fn apply_velocities(world: &World<Context>, ctx: &Context) {
+    let mut pos = world.get_mut::<Pos>();
+    let mut vel = world.get_mut::<Vel>();
+    let colliders = world.get::<Collider>();
+    let nudges = world.get::<Nudge>();
+    let solid = world.get::<Solid>();
+    let gates = world.get::<Gate>();
+    let falling = world.get::<Falling>();
+    let projectiles = world.get::<Projectile>();
+
+    let map = ctx.map.borrow();
+    let mut grid = blocker_grid(&pos, &colliders, &solid, &gates);
+
+    for i in 0..pos.len() {
+        let (from, vel) = match (pos[i], vel[i].as_mut()) {
+            (Some(pos), Some(vel)) if falling[i].is_none() && projectiles[i].is_none() => {
+                (pos, vel)
+            }
+            _ => continue,
+        };
+        let (x, y) = tile_under(from, colliders[i]);
+        let speed = floor_at(&map, x, y).speed;
+        let delta = Vel::new(vel.x * speed / 100, vel.y * speed / 100);
+
+        if colliders[i].is_some() {
+            let in_grid = is_blocker(i, &solid, &gates);
+            let (hit_x, hit_y) =
+                move_entity(i, delta, &mut pos, &colliders, &map, &mut grid, in_grid);
+            if hit_x {
+                vel.x = 0;
+            }
+            if hit_y {
+                vel.y = 0;
+            }
+
+            // Entities moving straight into a corner they only just clip get nudged around it.
+            let blocked = match (hit_x, hit_y) {
+                (true, _) if delta.y == 0 => Vel::new(delta.x, 0),
+                (_, true) if delta.x == 0 => Vel::new(0, delta.y),
+                _ => continue,
+            };
+            if let Some(Nudge { tolerance }) = nudges[i] {
+                let (from, collider) = (pos[i].unwrap(), colliders[i].unwrap());
+                let area_pos = Pos::new(from.x - tolerance, from.y - tolerance);
+                let area = Collider {
+                    w: collider.w + 2 * tolerance,
+                    h: collider.h + 2 * tolerance,
+                    ..collider
+                };
+                let blockers = nearby_blockers(&grid, i, area_pos, blocked, area, &pos, &colliders);
+                if let Some(shift) =
+                    corner_nudge(from, blocked, collider, tolerance, &map, &blockers)
+                {
+                    let speed = blocked.x.abs().max(blocked.y.abs());
+                    let step = Vel::new(shift.x.clamp(-speed, speed), shift.y.clamp(-speed, speed));
+                    move_entity(i, step, &mut pos, &colliders, &map, &mut grid, in_grid);
+                }
+            }
+        } else {
+            pos[i] = Some(Pos::new(from.x + delta.x, from.y + delta.y));
+        }
+    }
+}
+
+const PROJECTILE_SIZE: i32 = 2 * SUBPIXELS;
+/// How long arrows stay lodged in a wall.
+const STUCK_TICKS: u32 = 60;
+
+/// Fires a projectile from `origin`, which hits anything in `mask` other than its owner.
+fn spawn_projectile(
+    world: &World<Context>,
+    origin: Pos,
+    vel: Vel,
+    mask: u32,
+    projectile: Projectile,
+) {
+    let pos = Pos::new(
+        origin.x - PROJECTILE_SIZE / 2,
+        origin.y - PROJECTILE_SIZE / 2,
+    );
+    let collider = Collider {
+        layer: layer::PROJECTILE,
+        mask,
+        ..Collider::new(PROJECTILE_SIZE, PROJECTILE_SIZE)
+    };
+    world.spawn(|entity| {
+        entity
+            .with_component(pos)
+            .with_component(vel)
+            .with_component(collider)
+            .with_component(projectile)
+    });
+}
+
+pub fn fire_shooters(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let player = world.get::<Player>();
+    let mut shooters = world.get_mut::<Shooter>();
+    let effects = world.get::<StatusEffects>();
+
+    let map = ctx.map.borrow();
+    let Some(target) = player.iter().position(Option::is_some) else {
+        return;
+    };
+    let goal = centre(pos[target].unwrap(), colliders[target]);
+
+    for (i, shooter) in shooters.iter_mut().enumerate() {
+        // Shooters that have lost their collider are dead, and stop firing.
+        let (Some(shooter), Some(my_pos), Some(collider)) = (shooter, pos[i], colliders[i]) else {
+            continue;
+        };
+        if incapacitated(&effects, i) {
+            continue;
+        }
+        if shooter.cooldown > 0 {
+            shooter.cooldown -= 1;
+            continue;
+        }
+        let eye = centre(my_pos, Some(collider));
+        if raycast_tiles(&map, eye, goal).is_some() {
+            continue;
+        }
+
+        shooter.cooldown = shooter.interval;
+        let aim = Vec2::new((goal.x - eye.x) as f32, (goal.y - eye.y) as f32);
+        let aim = aim.y.atan2(aim.x);
+        let angles: Vec<f32> = match shooter.pattern {
+            Pattern::Aimed => vec![aim],
+            Pattern::Spread { count, angle } => {
+                let gap = angle / count.saturating_sub(1).max(1) as f32;
+                let first = aim - gap * count.saturating_sub(1) as f32 / 2.0;
+                (0..count).map(|n| first + gap * n as f32).collect()
+            }
+            Pattern::Ring { count } => {
+                let gap = TAU / count as f32;
+                (0..count).map(|n| aim + gap * n as f32).collect()
+            }
+        };
+        for angle in angles {
+            let dir = Vec2::from_angle(angle) * shooter.speed as f32;
+            let shot = Projectile {
+                owner: Some(i),
+                damage: shooter.damage,
+                lifetime: SHOT_LIFETIME,
+                pierce: 0,
+                sticks: false,
+                lodged: None,
+                hit: Vec::new(),
+            };
+            let vel = Vel::new(dir.x as i32, dir.y as i32);
+            spawn_projectile(world, eye, vel, layer::WORLD | layer::PLAYER, shot);
+        }
+    }
+}
+
+pub fn move_projectiles(world: &World<Context>, ctx: &Context) {
     let mut pos = world.get_mut::<Pos>();
     let vel = world.get::<Vel>();
+    let mut colliders = world.get_mut::<Collider>();
+    let mut projectiles = world.get_mut::<Projectile>();
+    let solid = world.get::<Solid>();
+    let gates = world.get::<Gate>();
+    let mut victims = Victims::borrow(world);
+
+    let map = ctx.map.borrow();
+    // Projectiles break against anything in their way, and hurt anything with health.
+    let grid = build_grid(&pos, &colliders, |i| {
+        is_blocker(i, &solid, &gates) || victims.health[i].is_some()
+    });
+
+    for i in 0..pos.len() {
+        let Some(projectile) = projectiles[i].as_mut() else {
+            continue;
+        };
+        if projectile.lifetime == 0 {
+            world.despawn(i);
+            continue;
+        }
+        projectile.lifetime -= 1;
+
+        // Arrows lodged in a wall have no collider, and wait to disappear unless the wall goes.
+        if let Some((x, y)) = projectile.lodged {
+            if !map.solid_at(x, y) {
+                world.despawn(i);
+            }
+            continue;
+        }
+        let (Some(from), Some(collider)) = (pos[i], colliders[i]) else {
+            continue;
+        };
+        let delta = vel[i].unwrap_or(Vel::new(0, 0));
+        let (to, hit_x) = sweep(from, Vel::new(delta.x, 0), collider, &map, &[]);
+        let (to, hit_y) = sweep(to, Vel::new(0, delta.y), collider, &map, &[]);
+        pos[i] = Some(to);
+        if hit_x.is_some() || hit_y.is_some() {
+            if projectile.sticks {
+                // Look a tile ahead along the blocked axis for the wall it went into.
+                let middle = centre(to, Some(collider));
+                let ahead = match hit_x {
+                    Some(_) => Pos::new(middle.x + delta.x.signum() * TILE, middle.y),
+                    None => Pos::new(middle.x, middle.y + delta.y.signum() * TILE),
+                };
+                projectile.lodged = raycast_tiles(&map, middle, ahead).map(|hit| hit.tile);
+                colliders[i] = None;
+                projectile.lifetime = STUCK_TICKS;
+            } else {
+                world.despawn(i);
+            }
+            continue;
+        }
+
+        for j in grid.query(to, collider) {
+            if projectile.owner == Some(j) || projectile.hit.contains(&j) {
+                continue;
+            }
+            let (other_pos, other_coll) = (pos[j].unwrap(), colliders[j].unwrap());
+            if !collider.collides_with(&other_coll)
+                || !overlaps(to, collider, other_pos, other_coll)
+            {
+                continue;
+            }
+            projectile.hit.push(j);
+            victims.hurt(j, projectile.damage);
+            if projectile.pierce == 0 {
+                world.despawn(i);
+                break;
+            }
+            projectile.pierce -= 1;
+        }
+    }
+}
+
+/// Places a lit bomb centred on `origin`.
+fn spawn_bomb(world: &World<Context>, origin: Pos) {
+    let pos = Pos::new(origin.x - BOMB_SIZE / 2, origin.y - BOMB_SIZE / 2);
+    let bomb = Bomb {
+        fuse: BOMB_FUSE,
+        radius: BOMB_RADIUS,
+        damage: BOMB_DAMAGE,
+    };
+    world.spawn(|entity| {
+        entity
+            .with_component(pos)
+            .with_component(Collider::new(BOMB_SIZE, BOMB_SIZE))
+            .with_component(bomb)
+    });
+}
+
+pub fn update_bombs(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let mut vel = world.get_mut::<Vel>();
     let colliders = world.get::<Collider>();
+    let mut bombs = world.get_mut::<Bomb>();
+    let mut victims = Victims::borrow(world);
+    let mut map = ctx.map.borrow_mut();
+
+    for i in 0..bombs.len() {
+        let Some(bomb) = bombs[i].as_mut() else {
+            continue;
+        };
+        if bomb.fuse > 0 {
+            bomb.fuse -= 1;
+            continue;
+        }
+
+        let origin = centre(pos[i].unwrap(), colliders[i]);
+        let blast_pos = Pos::new(origin.x - bomb.radius, origin.y - bomb.radius);
+        let blast = Collider::circle(bomb.radius);
+        let damage = Damage::new(bomb.damage, DamageKind::Explosive)
+            .with_status(Status::Burn, BOMB_BURN_TICKS);
+
+        for j in 0..pos.len() {
+            let (Some(other_pos), Some(other_coll)) = (pos[j], colliders[j]) else {
+                continue;
+            };
+            if j == i || !overlaps(blast_pos, blast, other_pos, other_coll) {
+                continue;
+            }
+            victims.hurt(j, damage);
+            // Only living things are thrown back, leaving blocks on the grid and shots on course.
+            if victims.health[j].is_none() {
+                continue;
+            }
+            if let Some(vel) = vel[j].as_mut() {
+                let offset = centre(other_pos, Some(other_coll));
+                let away = Vec2::new((offset.x - origin.x) as f32, (offset.y - origin.y) as f32);
+                let away = away.normalize_or_zero() * EXPLOSION_KNOCKBACK as f32;
+                vel.x = away.x as i32;
+                vel.y = away.y as i32;
+            }
+        }
+
+        // Blow open any cracked walls the blast reaches.
+        let tile = Collider::new(TILE - 1, TILE - 1);
+        for y in blast_pos.y.div_euclid(TILE)..=(blast_pos.y + blast.h).div_euclid(TILE) {
+            for x in blast_pos.x.div_euclid(TILE)..=(blast_pos.x + blast.w).div_euclid(TILE) {
+                if map.tile_at(x, y) == Some(Tile(tiles::CRACKED_WALL))
+                    && overlaps(blast_pos, blast, Pos::new(x * TILE, y * TILE), tile)
+                {
+                    map.set(x, y, tiles::OPENED_WALL);
+                }
+            }
+        }
+
+        let explosion = Explosion {
+            ticks: EXPLOSION_TICKS,
+            radius: bomb.radius,
+        };
+        world.despawn(i);
+        world.spawn(|entity| entity.with_component(origin).with_component(explosion));
+    }
+}
 
-    for data in izip!(pos.iter_mut(), vel.iter(), colliders.iter()) {
-        if let (Some(pos), Some(vel), Some(collider)) = data {
-            if !collide((pos.x + vel.x, pos.y), *collider, ctx.map) {
-                pos.x += vel.x;
+pub fn update_explosions(world: &World<Context>, _ctx: &Context) {
+    let mut explosions = world.get_mut::<Explosion>();
+
+    for (i, explosion) in explosions.iter_mut().enumerate() {
+        if let Some(explosion) = explosion {
+            if explosion.ticks > 0 {
+                explosion.ticks -= 1;
+            } else {
+                world.despawn(i);
             }
-            if !collide((pos.x, pos.y + vel.y), *collider, ctx.map) {
-                pos.y += vel.y;
+        }
+    }
+}
+
+pub fn leave_room(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let player = world.get::<Player>();
+    let mut exit = world.resource_mut::<RoomExit>();
+    let map = ctx.map.borrow();
+
+    for data in izip!(pos.iter(), colliders.iter(), player.iter()) {
+        if let (Some(pos), collider, Some(_player)) = data {
+            let (x, y) = tile_under(*pos, *collider);
+            if let Some(destination) = map.exit_at(x, y) {
+                exit.0 = Some(destination);
             }
-        } else if let (Some(pos), Some(vel), _) = data {
-            pos.x += vel.x;
-            pos.y += vel.y;
         }
     }
 }
 
-pub fn decelerate(world: &World<Context>, _ctx: &Context) {
+pub fn apply_conveyors(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
     let mut vel = world.get_mut::<Vel>();
+    let colliders = world.get::<Collider>();
+    let projectiles = world.get::<Projectile>();
+    let map = ctx.map.borrow();
 
-    for vel in vel.iter_mut().flatten() {
-        vel.x -= vel.x.signum();
-        vel.y -= vel.y.signum();
+    for data in izip!(
+        pos.iter(),
+        vel.iter_mut(),
+        colliders.iter(),
+        projectiles.iter()
+    ) {
+        if let (Some(pos), Some(vel), collider, None) = data {
+            let (x, y) = tile_under(*pos, *collider);
+            let (dx, dy) = floor_at(&map, x, y).drift;
+            vel.x += dx;
+            vel.y += dy;
+        }
     }
 }
 
-pub fn update_health(world: &World<Context>, _ctx: &Context) {
-    let mut health = world.get_mut::<Health>();
-    let mut mods = world.get_mut::<HealthMod>();
+pub fn decelerate(world: &World<Context>, ctx: &Context) {
     let pos = world.get::<Pos>();
     let mut vel = world.get_mut::<Vel>();
     let colliders = world.get::<Collider>();
+    let projectiles = world.get::<Projectile>();
+    let map = ctx.map.borrow();
 
-    for (i, health) in health.iter_mut().enumerate() {
-        if health.is_none() || colliders[i].is_none() {
+    for data in izip!(
+        pos.iter(),
+        vel.iter_mut(),
+        colliders.iter(),
+        projectiles.iter()
+    ) {
+        if let (pos, Some(vel), collider, None) = data {
+            let friction = match pos {
+                Some(pos) => {
+                    let (x, y) = tile_under(*pos, *collider);
+                    floor_at(&map, x, y).friction
+                }
+                None => tiles::FLOORS[0].friction,
+            };
+            vel.x -= vel.x.signum() * vel.x.abs().min(friction);
+            vel.y -= vel.y.signum() * vel.y.abs().min(friction);
+        }
+    }
+}
+
+const INVULNERABLE_TICKS: u32 = 20;
+
+/// The components involved in hurting entities, borrowed together for systems that deal damage.
+struct Victims<'a> {
+    health: RefMut<'a, Vec<Option<Health>>>,
+    invulnerable: RefMut<'a, Vec<Option<Invulnerable>>>,
+    iframes: Ref<'a, Vec<Option<IFrames>>>,
+    resistances: Ref<'a, Vec<Option<Resistances>>>,
+    effects: RefMut<'a, Vec<Option<StatusEffects>>>,
+}
+
+impl<'a> Victims<'a> {
+    fn borrow(world: &'a World<Context>) -> Self {
+        Self {
+            health: world.get_mut::<Health>(),
+            invulnerable: world.get_mut::<Invulnerable>(),
+            iframes: world.get::<IFrames>(),
+            resistances: world.get::<Resistances>(),
+            effects: world.get_mut::<StatusEffects>(),
+        }
+    }
+
+    /// Deals damage to an entity, scaled by its resistances, unless it is still invulnerable from
+    /// an earlier hit, and then makes it invulnerable for a while. Returns whether the hit landed.
+    fn hurt(&mut self, entity: usize, damage: Damage) -> bool {
+        if self.invulnerable[entity].is_some() || !self.wound(entity, damage) {
+            return false;
+        }
+        let ticks = self.iframes[entity].map_or(INVULNERABLE_TICKS, |frames| frames.ticks);
+        self.invulnerable[entity] = Some(Invulnerable { ticks });
+        true
+    }
+
+    /// Deals damage to an entity, scaled by its resistances, regardless of whether it is
+    /// invulnerable. Used for damage over time, which shouldn't shield against other hits.
+    fn wound(&mut self, entity: usize, damage: Damage) -> bool {
+        let Some(health) = self.health[entity].as_mut() else {
+            return false;
+        };
+        let amount = match &self.resistances[entity] {
+            Some(resistances) => resistances.apply(damage),
+            None => damage.amount,
+        };
+        if amount <= 0 {
+            return false;
+        }
+        health.current -= amount;
+        if let Some((status, ticks)) = damage.status {
+            self.effects[entity]
+                .get_or_insert_with(StatusEffects::default)
+                .apply(status, ticks);
+        }
+        true
+    }
+}
+
+/// Checks whether an entity is stunned or frozen.
+fn incapacitated(effects: &[Option<StatusEffects>], entity: usize) -> bool {
+    effects[entity]
+        .as_ref()
+        .is_some_and(StatusEffects::incapacitated)
+}
+
+const POISON_INTERVAL: u32 = 40;
+/// The damage poison deals each interval for every stack.
+const POISON_DAMAGE: i32 = 2;
+const BURN_INTERVAL: u32 = 30;
+const BURN_DAMAGE: i32 = 2;
+const FIRE_TICKS: u32 = 90;
+/// How long a fire burns before catching neighbouring tiles.
+const FIRE_SPREAD_TICKS: u32 = 60;
+const FIRE_BURN_TICKS: u32 = 90;
+
+pub fn update_status_effects(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let mut vel = world.get_mut::<Vel>();
+    let mut victims = Victims::borrow(world);
+    let mut map = ctx.map.borrow_mut();
+
+    for i in 0..victims.effects.len() {
+        let Some(mut effects) = victims.effects[i].take() else {
             continue;
+        };
+        for effect in &mut effects.0 {
+            effect.ticks = effect.ticks.saturating_sub(1);
+            match effect.status {
+                Status::Poison if effect.ticks % POISON_INTERVAL == 0 => {
+                    let damage =
+                        Damage::new(POISON_DAMAGE * effect.stacks as i32, DamageKind::Poison);
+                    victims.wound(i, damage);
+                }
+                Status::Burn if effect.ticks % BURN_INTERVAL == 0 => {
+                    victims.wound(i, Damage::new(BURN_DAMAGE, DamageKind::Fire));
+                }
+                _ => {}
+            }
         }
-        let health = health.as_mut().unwrap();
-        for (j, modifier) in mods.iter_mut().enumerate() {
-            if i == j || modifier.is_none() || colliders[j].is_none() {
+        if effects.has(Status::Freeze) {
+            if let Some(vel) = vel[i].as_mut() {
+                *vel = Vel::new(0, 0);
+            }
+        }
+        // Burning entities set alight any flammable floor they stand on.
+        if let (true, Some(pos)) = (effects.has(Status::Burn), pos[i]) {
+            let (x, y) = tile_under(pos, colliders[i]);
+            ignite(world, &mut map, x, y);
+        }
+
+        effects.0.retain(|effect| effect.ticks > 0);
+        if !effects.0.is_empty() {
+            victims.effects[i] = Some(effects);
+        }
+    }
+}
+
+/// Starts a fire on a flammable tile, burning it away to bare floor.
+fn ignite(world: &World<Context>, map: &mut Tilemap, x: i32, y: i32) {
+    if map.tile_at(x, y) != Some(Tile(tiles::DRY_GRASS)) {
+        return;
+    }
+    map.set(x, y, tiles::BURNT_GRASS);
+    let collider = Collider {
+        layer: layer::WORLD,
+        mask: layer::PLAYER | layer::ENEMY,
+        ..Collider::new(TILE - 1, TILE - 1)
+    };
+    world.spawn(|entity| {
+        entity
+            .with_component(Pos::new(x * TILE, y * TILE))
+            .with_component(collider)
+            .with_component(Fire { ticks: FIRE_TICKS })
+    });
+}
+
+pub fn update_fires(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let mut fires = world.get_mut::<Fire>();
+    let mut effects = world.get_mut::<StatusEffects>();
+    let mut map = ctx.map.borrow_mut();
+
+    for i in 0..fires.len() {
+        let Some(fire) = fires[i].as_mut() else {
+            continue;
+        };
+        if fire.ticks == 0 {
+            world.despawn(i);
+            continue;
+        }
+        fire.ticks -= 1;
+
+        let (fire_pos, fire_coll) = (pos[i].unwrap(), colliders[i].unwrap());
+        if fire.ticks == FIRE_TICKS - FIRE_SPREAD_TICKS {
+            let (x, y) = (fire_pos.x.div_euclid(TILE), fire_pos.y.div_euclid(TILE));
+            for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+                ignite(world, &mut map, x + dx, y + dy);
+            }
+        }
+
+        for j in 0..pos.len() {
+            let (Some(other_pos), Some(other_coll)) = (pos[j], colliders[j]) else {
                 continue;
+            };
+            if fire_coll.collides_with(&other_coll)
+                && overlaps(fire_pos, fire_coll, other_pos, other_coll)
+            {
+                effects[j]
+                    .get_or_insert_with(StatusEffects::default)
+                    .apply(Status::Burn, FIRE_BURN_TICKS);
             }
-            let modifier = modifier.as_mut().unwrap();
-            if modifier.cooldown > 0 {
+        }
+    }
+}
+
+pub fn update_health(world: &World<Context>, _ctx: &Context) {
+    let mut victims = Victims::borrow(world);
+    let mods = world.get::<HealthMod>();
+    let pos = world.get::<Pos>();
+    let mut vel = world.get_mut::<Vel>();
+    let colliders = world.get::<Collider>();
+
+    for i in 0..victims.health.len() {
+        if victims.health[i].is_none() || colliders[i].is_none() {
+            continue;
+        }
+        for (j, modifier) in mods.iter().enumerate() {
+            if i == j || modifier.is_none() || colliders[j].is_none() {
                 continue;
             }
+            let modifier = modifier.as_ref().unwrap();
 
-            if aabb(
-                pos[i].unwrap(),
-                colliders[i].unwrap(),
-                pos[j].unwrap(),
-                colliders[j].unwrap(),
-            ) {
-                health.0 += modifier.health;
-                modifier.cooldown = 20;
+            if colliders[j].unwrap().collides_with(&colliders[i].unwrap())
+                && touching(
+                    pos[i].unwrap(),
+                    colliders[i].unwrap(),
+                    pos[j].unwrap(),
+                    colliders[j].unwrap(),
+                )
+            {
+                if modifier.health >= 0 {
+                    victims.health[i].as_mut().unwrap().heal(modifier.health);
+                    continue;
+                }
+                let damage = Damage {
+                    status: modifier.status,
+                    ..Damage::new(-modifier.health, modifier.kind)
+                };
+                if !victims.hurt(i, damage) {
+                    continue;
+                }
                 if let Some(ref mut vel) = vel[i] {
                     vel.x = (pos[i].unwrap().x - pos[j].unwrap().x) * 2 / 3;
                     vel.y = (pos[i].unwrap().y - pos[j].unwrap().y) * 2 / 3;
@@ -138,94 +1030,833 @@ pub fn update_health(world: &World<Context>, _ctx: &Context) {
             }
         }
     }
+}
 
-    for modifier in mods.iter_mut().flatten() {
-        if modifier.cooldown > 0 {
-            modifier.cooldown -= 1;
+pub fn update_invulnerability(world: &World<Context>, _ctx: &Context) {
+    let mut invulnerable = world.get_mut::<Invulnerable>();
+
+    for state in invulnerable.iter_mut() {
+        if let Some(Invulnerable { ticks }) = state {
+            if *ticks > 0 {
+                *ticks -= 1;
+            } else {
+                *state = None;
+            }
         }
     }
 }
 
-pub fn move_followers(world: &World<Context>, _ctx: &Context) {
+pub fn move_followers(world: &World<Context>, ctx: &Context) {
     let pos = world.get::<Pos>();
     let mut vel = world.get_mut::<Vel>();
     let follow = world.get::<Follow>();
+    let colliders = world.get::<Collider>();
+    let solid = world.get::<Solid>();
+    let gates = world.get::<Gate>();
+    let effects = world.get::<StatusEffects>();
 
-    for data in izip!(pos.iter(), vel.iter_mut(), follow.iter()) {
+    let map = ctx.map.borrow();
+    let centre = |i: usize| {
+        let Collider { w, h, .. } = colliders[i].unwrap_or(Collider::new(0, 0));
+        let pos = pos[i].unwrap();
+        Pos::new(pos.x + w / 2, pos.y + h / 2)
+    };
+
+    for (i, data) in izip!(pos.iter(), vel.iter_mut(), follow.iter()).enumerate() {
         if let (Some(my_pos), Some(vel), Some(follow)) = data {
-            if pos[follow.0].is_none() {
+            if pos[follow.target].is_none() {
                 panic!("attempted to follow position-less entity");
             }
-            let other_pos = pos[follow.0].unwrap();
-            vel.x = cmp::max_by((other_pos.x - my_pos.x).signum(), vel.x, |a, b| a.abs().cmp(&b.abs()));
-            vel.y = cmp::max_by((other_pos.y - my_pos.y).signum(), vel.y, |a, b| a.abs().cmp(&b.abs()));
+            if incapacitated(&effects, i) {
+                continue;
+            }
+
+            // Followers only give chase while nothing stands between them and their target.
+            let (eye, goal) = (centre(i), centre(follow.target));
+            let wall = raycast_tiles(&map, eye, goal);
+            let first = raycast_entities(eye, goal, &pos, &colliders, |j| {
+                j != i && (j == follow.target || is_blocker(j, &solid, &gates))
+            });
+            let in_sight = match first {
+                Some(hit) => {
+                    hit.entity == follow.target && wall.is_none_or(|w| w.distance > hit.distance)
+                }
+                None => wall.is_none(),
+            };
+            if !in_sight {
+                continue;
+            }
+
+            let other_pos = pos[follow.target].unwrap();
+            let dx = (other_pos.x - my_pos.x).clamp(-follow.speed, follow.speed);
+            let dy = (other_pos.y - my_pos.y).clamp(-follow.speed, follow.speed);
+            vel.x = cmp::max_by(dx, vel.x, |a, b| a.abs().cmp(&b.abs()));
+            vel.y = cmp::max_by(dy, vel.y, |a, b| a.abs().cmp(&b.abs()));
         }
     }
 }
 
+const PUSH_TICKS: u32 = 20;
+const SLIDE_SPEED: i32 = SUBPIXELS;
 pub fn move_pushables(world: &World<Context>, ctx: &Context) {
     let mut pos = world.get_mut::<Pos>();
     let vel = world.get::<Vel>();
     let colliders = world.get::<Collider>();
+    let mut push = world.get_mut::<Push>();
+    let pushers = world.get::<Pusher>();
+    let solid = world.get::<Solid>();
+    let gates = world.get::<Gate>();
+    let falling = world.get::<Falling>();
+
+    let map = ctx.map.borrow();
+    // Blocks are stopped by other pushables and enemies, as well as anything solid.
+    let mut grid = build_grid(&pos, &colliders, |i| {
+        is_blocker(i, &solid, &gates)
+            || push[i].is_some()
+            || colliders[i].unwrap().layer & layer::ENEMY != 0
+    });
+
+    // Slide the front block of each row first, so that the blocks behind don't run into it.
+    let mut sliding: Vec<usize> = (0..pos.len())
+        .filter(|&i| {
+            falling[i].is_none() && matches!(push[i], Some(Push { slide, .. }) if slide > 0)
+        })
+        .collect();
+    sliding.sort_by_key(|&i| {
+        let (pos, dir) = (pos[i].unwrap(), push[i].as_ref().unwrap().dir);
+        -(pos.x * dir.0 + pos.y * dir.1)
+    });
+    for i in sliding {
+        let block = push[i].as_mut().unwrap();
+        let step = block.slide.min(SLIDE_SPEED);
+        let delta = Vel::new(block.dir.0 * step, block.dir.1 * step);
+        let from = pos[i].unwrap();
+        move_entity(i, delta, &mut pos, &colliders, &map, &mut grid, true);
+        // Anything that moves into the way holds the slide up until it clears, rather than cutting
+        // it short, so that the block still comes to rest on the grid.
+        let to = pos[i].unwrap();
+        block.slide -= (to.x - from.x).abs() + (to.y - from.y).abs();
+    }
+
+    for i in 0..pos.len() {
+        let (block_pos, block_coll) = match (pos[i], colliders[i], &push[i]) {
+            (Some(pos), Some(coll), Some(block)) if falling[i].is_none() && block.slide == 0 => {
+                (pos, coll)
+            }
+            _ => continue,
+        };
+
+        let delta = izip!(pos.iter(), vel.iter(), colliders.iter(), pushers.iter())
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .find_map(|(_, data)| match data {
+                (Some(pusher_pos), Some(vel), Some(pusher_coll), Some(_pusher)) => {
+                    push_delta(*pusher_pos, *vel, *pusher_coll, block_pos, block_coll)
+                }
+                _ => None,
+            });
+
+        // Blocks only move when pushed straight along one axis in an allowed direction.
+        let block = push[i].as_mut().unwrap();
+        let dir = match delta {
+            Some(delta) if (delta.x == 0) != (delta.y == 0) => (delta.x.signum(), delta.y.signum()),
+            _ => (0, 0),
+        };
+        if dir == (0, 0) || !block.can_move(dir) {
+            block.progress = 0;
+            continue;
+        }
+        if dir != block.dir {
+            block.dir = dir;
+            block.progress = 0;
+        }
+        block.progress += 1;
+        if block.progress < PUSH_TICKS {
+            continue;
+        }
+        block.progress = 0;
+
+        let max_chain = block.max_chain;
+        let chain = match push_chain(i, dir, max_chain, &pos, &colliders, &push, &falling) {
+            Some(chain) => chain,
+            None => continue,
+        };
+        if chain
+            .iter()
+            .any(|&j| !push[j].as_ref().unwrap().can_move(dir))
+        {
+            continue;
+        }
+
+        // Only the front of the row needs a clear path.
+        let front = *chain.last().unwrap();
+        let (front_pos, front_coll) = (pos[front].unwrap(), colliders[front].unwrap());
+        let delta = Vel::new(dir.0 * TILE, dir.1 * TILE);
+        let blockers =
+            nearby_blockers(&grid, front, front_pos, delta, front_coll, &pos, &colliders);
+        let (_, hit) = sweep(front_pos, delta, front_coll, &map, &blockers);
+        if hit.is_some() {
+            continue;
+        }
+        for j in chain {
+            let block = push[j].as_mut().unwrap();
+            block.dir = dir;
+            block.slide = TILE;
+            block.pushed = true;
+        }
+    }
+}
+
+/// Collects the row of pushables that would move if `first` were pushed in `dir`, starting with
+/// `first` itself.
+///
+/// Returns `None` if the row is longer than `max_len`.
+fn push_chain(
+    first: usize,
+    dir: (i32, i32),
+    max_len: usize,
+    pos: &[Option<Pos>],
+    colliders: &[Option<Collider>],
+    push: &[Option<Push>],
+    falling: &[Option<Falling>],
+) -> Option<Vec<usize>> {
+    let dir_vel = Vel::new(dir.0, dir.1);
+    let mut chain = vec![first];
+    loop {
+        let last = *chain.last().unwrap();
+        let (last_pos, last_coll) = (pos[last].unwrap(), colliders[last].unwrap());
+        let next = (0..pos.len()).find(|&j| match (pos[j], colliders[j], &push[j], &falling[j]) {
+            (Some(pos), Some(coll), Some(_push), None) if !chain.contains(&j) => {
+                push_delta(last_pos, dir_vel, last_coll, pos, coll).is_some()
+            }
+            _ => false,
+        });
+        match next {
+            Some(_) if chain.len() == max_len => return None,
+            Some(next) => chain.push(next),
+            None => return Some(chain),
+        }
+    }
+}
+
+/// Works out which way a pusher moving with `vel` is shoving a block it is touching.
+///
+/// Only the components of `vel` heading into the block count, so brushing past a block's corner or
+/// walking away from it leaves it in place.
+fn push_delta(
+    pusher_pos: Pos,
+    vel: Vel,
+    pusher_coll: Collider,
+    block_pos: Pos,
+    block_coll: Collider,
+) -> Option<Vel> {
+    if !pusher_coll.collides_with(&block_coll)
+        || !touching(pusher_pos, pusher_coll, block_pos, block_coll)
+    {
+        return None;
+    }
+    let rows_overlap =
+        pusher_pos.y <= block_pos.y + block_coll.h && pusher_pos.y + pusher_coll.h >= block_pos.y;
+    let cols_overlap =
+        pusher_pos.x <= block_pos.x + block_coll.w && pusher_pos.x + pusher_coll.w >= block_pos.x;
+    let towards = |v: i32, from: i32, to: i32| (v > 0 && from < to) || (v < 0 && from > to);
+
+    let mut delta = Vel::new(0, 0);
+    if rows_overlap && towards(vel.x, pusher_pos.x, block_pos.x) {
+        delta.x = vel.x;
+    }
+    if cols_overlap && towards(vel.y, pusher_pos.y, block_pos.y) {
+        delta.y = vel.y;
+    }
+    (delta.x != 0 || delta.y != 0).then_some(delta)
+}
+
+pub fn update_plates(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
     let push = world.get::<Push>();
     let player = world.get::<Player>();
+    let mut plates = world.get_mut::<PressurePlate>();
 
-    let player_idx = player
-        .iter()
-        .position(Option::is_some)
-        .expect("Player entity missing.");
-    let player_pos = pos[player_idx].unwrap();
-    let player_coll = match colliders[player_idx]{
-        Some(coll) => coll,
-        None => return,
-    };
-    let player_vel = vel[player_idx].unwrap();
-
-    let mut new_pos = vec![None; pos.len()];
-    let mut new_player_pos = player_pos;
-
-    for (i, data) in izip!(pos.iter(), colliders.iter(), push.iter()).enumerate() {
-        if let (Some(push_pos), Some(push_coll), Some(_push)) = data {
-            if aabb(*push_pos, *push_coll, player_pos, player_coll) {
-                if !collide(
-                    (push_pos.x + player_vel.x, push_pos.y + player_vel.y),
-                    *push_coll,
-                    ctx.map,
-                ) {
-                    new_pos[i] = Some(Pos {
-                        x: push_pos.x + player_vel.x,
-                        y: push_pos.y + player_vel.y,
-                    })
+    // Weights are single points at the centre of each collider.
+    let weights: Vec<(Pos, Collider)> = izip!(pos.iter(), colliders.iter(), push.iter())
+        .zip(player.iter())
+        .filter_map(|(data, player)| match data {
+            (Some(pos), Some(coll), push) if push.is_some() || player.is_some() => {
+                let centre = Pos::new(pos.x + coll.w / 2, pos.y + coll.h / 2);
+                let point = Collider {
+                    w: 0,
+                    h: 0,
+                    ..*coll
+                };
+                Some((centre, point))
+            }
+            _ => None,
+        })
+        .collect();
+
+    for data in izip!(pos.iter(), colliders.iter(), plates.iter_mut()) {
+        if let (Some(plate_pos), Some(plate_coll), Some(plate)) = data {
+            plate.active = weights.iter().any(|(centre, weight)| {
+                plate_coll.collides_with(weight)
+                    && overlaps(*centre, *weight, *plate_pos, *plate_coll)
+            });
+        }
+    }
+}
+
+pub fn update_triggers(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let mut triggers = world.get_mut::<Trigger>();
+    let mut events = world.resource_mut::<TriggerEvents>();
+
+    events.0.clear();
+
+    let grid = build_grid(&pos, &colliders, |_| true);
+
+    for (i, data) in izip!(pos.iter(), colliders.iter(), triggers.iter_mut()).enumerate() {
+        if let (Some(trigger_pos), Some(trigger_coll), Some(trigger)) = data {
+            let inside: Vec<usize> = grid
+                .query(*trigger_pos, *trigger_coll)
+                .into_iter()
+                .filter(|&j| j != i)
+                .filter(|&j| {
+                    let (other_pos, other_coll) = (pos[j].unwrap(), colliders[j].unwrap());
+                    trigger_coll.collides_with(&other_coll)
+                        && overlaps(*trigger_pos, *trigger_coll, other_pos, other_coll)
+                })
+                .collect();
+
+            for &other in &inside {
+                let phase = if trigger.inside.contains(&other) {
+                    TriggerPhase::Stay
                 } else {
-                    new_player_pos = Pos {
-                        x: player_pos.x - player_vel.x,
-                        y: player_pos.y - player_vel.y,
-                    };
+                    TriggerPhase::Enter
+                };
+                events.0.push(TriggerEvent {
+                    phase,
+                    trigger: i,
+                    other,
+                });
+            }
+            for &other in trigger.inside.iter().filter(|j| !inside.contains(j)) {
+                events.0.push(TriggerEvent {
+                    phase: TriggerPhase::Exit,
+                    trigger: i,
+                    other,
+                });
+            }
+            trigger.inside = inside;
+        }
+    }
+}
+
+pub fn fire_triggers(world: &World<Context>, _ctx: &Context) {
+    let player = world.get::<Player>();
+    let mut triggers = world.get_mut::<Trigger>();
+    let events = world.resource::<TriggerEvents>();
+
+    for event in &events.0 {
+        if event.phase == TriggerPhase::Enter && player[event.other].is_some() {
+            if let Some(ref mut trigger) = triggers[event.trigger] {
+                trigger.fired = true;
+            }
+        }
+    }
+}
+
+/// Moves each boss on to the phase its health calls for, swapping in that phase's behaviour.
+pub fn update_bosses(world: &World<Context>, _ctx: &Context) {
+    let mut bosses = world.get_mut::<Boss>();
+    let health = world.get::<Health>();
+    let mut follow = world.get_mut::<Follow>();
+    let mut shooters = world.get_mut::<Shooter>();
+
+    for (i, data) in izip!(bosses.iter_mut(), health.iter()).enumerate() {
+        let (Some(boss), Some(health)) = data else {
+            continue;
+        };
+        if health.current <= 0 {
+            continue;
+        }
+        let phase = boss
+            .phases
+            .iter()
+            .rposition(|phase| health.current <= phase.health)
+            .unwrap_or(0);
+        if boss.phase == Some(phase) {
+            continue;
+        }
+        boss.phase = Some(phase);
+
+        let phase = &boss.phases[phase];
+        if let (Some(follow), Some(speed)) = (&mut follow[i], phase.speed) {
+            follow.speed = speed;
+        }
+        shooters[i] = phase.shooter.clone();
+    }
+}
+
+pub fn update_gates(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let plates = world.get::<PressurePlate>();
+    let switches = world.get::<Switch>();
+    let triggers = world.get::<Trigger>();
+    let bosses = world.get::<Boss>();
+    let health = world.get::<Health>();
+    let mut gates = world.get_mut::<Gate>();
+    let mut spr = world.get_mut::<Spr>();
+
+    for (pos, gate, spr) in izip!(pos.iter(), gates.iter_mut(), spr.iter_mut()) {
+        if let Some(gate) = gate {
+            let active = plates
+                .iter()
+                .flatten()
+                .any(|plate| plate.active && plate.target == gate.name)
+                || switches
+                    .iter()
+                    .flatten()
+                    .any(|switch| switch.on && switch.target == gate.name)
+                || triggers
+                    .iter()
+                    .flatten()
+                    .any(|trigger| trigger.fired && trigger.target.as_ref() == Some(&gate.name));
+            // A living boss holds its arena shut, whatever else is acting on the gate.
+            let locked = izip!(bosses.iter(), health.iter()).any(|data| match data {
+                (Some(boss), Some(health)) => {
+                    health.current > 0 && boss.arena.as_ref() == Some(&gate.name)
                 }
+                _ => false,
+            });
+            gate.open = !locked && active != gate.inverted;
+            let id = if gate.open {
+                gate.open_spr
+            } else {
+                gate.closed_spr
+            };
+            *spr = id.map(Spr);
+
+            let tile = if gate.open {
+                gate.open_tile
+            } else {
+                gate.closed_tile
+            };
+            if let (Some(pos), Some(tile)) = (pos, tile) {
+                let (x, y) = (pos.x.div_euclid(TILE), pos.y.div_euclid(TILE));
+                ctx.map.borrow_mut().set(x, y, tile);
             }
         }
     }
+}
 
-    pos[player_idx] = Some(new_player_pos);
+pub const FALL_TICKS: u32 = 30;
+pub fn fall_into_pits(world: &World<Context>, ctx: &Context) {
+    let mut pos = world.get_mut::<Pos>();
+    let mut vel = world.get_mut::<Vel>();
+    let mut spr = world.get_mut::<Spr>();
+    let mut colliders = world.get_mut::<Collider>();
+    let mut victims = Victims::borrow(world);
+    let mut push = world.get_mut::<Push>();
+    let mut player = world.get_mut::<Player>();
+    let mut falling = world.get_mut::<Falling>();
+    let projectiles = world.get::<Projectile>();
+    let entrance = world.resource::<Entrance>();
+    let mut map = ctx.map.borrow_mut();
 
-    for (pos, new_pos) in pos.iter_mut().zip(new_pos.iter()) {
-        if let Some(new_pos) = new_pos {
-            *pos = Some(*new_pos);
+    for i in 0..pos.len() {
+        if projectiles[i].is_some() {
+            continue;
+        }
+        let (entity_pos, collider) = match (pos[i], colliders[i]) {
+            (Some(pos), Some(collider)) if vel[i].is_some() || push[i].is_some() => (pos, collider),
+            _ => continue,
+        };
+        let (x, y) = tile_under(entity_pos, Some(collider));
+
+        match falling[i] {
+            None if map.tile_at(x, y) == Some(Tile(tiles::PIT)) => {
+                falling[i] = Some(Falling { ticks: FALL_TICKS });
+                if let Some(ref mut vel) = vel[i] {
+                    *vel = Vel::new(0, 0);
+                }
+            }
+            None => {
+                if let Some(ref mut player) = player[i] {
+                    player.safe_pos = Some(entity_pos);
+                }
+            }
+            Some(ref mut fall) if fall.ticks > 0 => fall.ticks -= 1,
+            Some(_) => {
+                falling[i] = None;
+                if push[i].is_some() {
+                    map.set(x, y, tiles::FILLED_PIT);
+                    spr[i] = None;
+                    colliders[i] = None;
+                    push[i] = None;
+                } else if let Some(ref player) = player[i] {
+                    // Falling always hurts, even while invulnerable.
+                    victims.wound(i, Damage::new(2, DamageKind::Fall));
+                    if let Some(respawn) = player.safe_pos.or(entrance.0) {
+                        pos[i] = Some(respawn);
+                    }
+                } else {
+                    // Anything else that falls in is gone for good, and counts as killed, so that
+                    // a boss lost to a pit still opens its arena.
+                    if let Some(health) = victims.health[i].as_mut() {
+                        health.current = 0;
+                    }
+                    spr[i] = None;
+                    colliders[i] = None;
+                }
+            }
         }
     }
 }
 
 pub fn remove_dead(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
     let mut spr = world.get_mut::<Spr>();
     let mut colliders = world.get_mut::<Collider>();
     let health = world.get::<Health>();
+    let mut loot = world.get_mut::<Loot>();
+    let player = world.get::<Player>();
+    let mut dying = world.get_mut::<Dying>();
+    let mut rng = world.resource_mut::<Rng>();
 
-    for (spr, collider, health) in izip!(spr.iter_mut(), colliders.iter_mut(), health.iter()) {
-        if let Some(health) = health {
-            if health.0 <= 0 {
-                *spr = None;
-                *collider = None;
+    for i in 0..health.len() {
+        if !matches!(health[i], Some(Health { current, .. }) if current <= 0) {
+            continue;
+        }
+        // The player stays around to play out their death.
+        if player[i].is_some() {
+            if dying[i].is_none() {
+                dying[i] = Some(Dying { ticks: DEATH_TICKS });
+            }
+            continue;
+        }
+        // Roll for a drop only once, while the body is still where it fell.
+        if let (Some(table), Some(pos)) = (loot[i].take(), pos[i]) {
+            if let Some(entry) = roll_loot(&table, &mut rng) {
+                spawn_pickup(world, centre(pos, colliders[i]), entry);
             }
         }
+        spr[i] = None;
+        colliders[i] = None;
+    }
+}
+
+pub const DEATH_TICKS: u32 = 60;
+
+pub fn update_dying(world: &World<Context>, _ctx: &Context) {
+    let mut vel = world.get_mut::<Vel>();
+    let mut dying = world.get_mut::<Dying>();
+    let mut game_over = world.resource_mut::<GameOver>();
+
+    for (vel, dying) in izip!(vel.iter_mut(), dying.iter_mut()) {
+        if let Some(dying) = dying {
+            if let Some(vel) = vel {
+                *vel = Vel::new(0, 0);
+            }
+            if dying.ticks > 0 {
+                dying.ticks -= 1;
+            } else {
+                game_over.0 = true;
+            }
+        }
+    }
+}
+
+pub fn touch_checkpoints(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let player = world.get::<Player>();
+    let mut checkpoints = world.get_mut::<Checkpoint>();
+    let mut reached = world.resource_mut::<CheckpointReached>();
+
+    let Some(player_idx) = player.iter().position(Option::is_some) else {
+        return;
+    };
+    let (Some(player_pos), Some(player_coll)) = (pos[player_idx], colliders[player_idx]) else {
+        return;
+    };
+
+    let touched = (0..checkpoints.len()).find(|&i| {
+        matches!(checkpoints[i], Some(Checkpoint { active: false }))
+            && match (pos[i], colliders[i]) {
+                (Some(pos), Some(coll)) => overlaps(pos, coll, player_pos, player_coll),
+                _ => false,
+            }
+    });
+    if let Some(touched) = touched {
+        // Only the most recent checkpoint counts.
+        for (i, checkpoint) in checkpoints.iter_mut().enumerate() {
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.active = i == touched;
+            }
+        }
+        reached.0 = pos[touched];
+    }
+}
+
+/// Picks an entry from a loot table, returning `None` if it comes up empty.
+fn roll_loot(table: &Loot, rng: &mut Rng) -> Option<LootEntry> {
+    let total: u32 = table.0.iter().map(|entry| entry.weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = rng.below(total);
+    for entry in &table.0 {
+        if roll < entry.weight {
+            return entry.item.is_some().then_some(*entry);
+        }
+        roll -= entry.weight;
+    }
+    None
+}
+
+const PICKUP_SIZE: i32 = 8 * SUBPIXELS;
+const PICKUP_TICKS: u32 = 600;
+/// How long before disappearing a pickup starts blinking.
+pub const PICKUP_WARNING_TICKS: u32 = 120;
+
+fn spawn_pickup(world: &World<Context>, origin: Pos, entry: LootEntry) {
+    let pos = Pos::new(origin.x - PICKUP_SIZE / 2, origin.y - PICKUP_SIZE / 2);
+    let collider = Collider {
+        layer: layer::PICKUP,
+        mask: layer::PLAYER,
+        ..Collider::new(PICKUP_SIZE, PICKUP_SIZE)
+    };
+    let pickup = Pickup {
+        item: entry.item.unwrap(),
+        amount: entry.amount,
+        ticks: PICKUP_TICKS,
+    };
+    world.spawn(|entity| {
+        entity
+            .with_component(pos)
+            .with_component(collider)
+            .with_component(pickup)
+    });
+}
+
+pub fn collect_pickups(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let mut pickups = world.get_mut::<Pickup>();
+    let mut player = world.get_mut::<Player>();
+    let mut health = world.get_mut::<Health>();
+
+    let Some(player_idx) = player.iter().position(Option::is_some) else {
+        return;
+    };
+    let player = player[player_idx].as_mut().unwrap();
+    let player_pos = pos[player_idx].unwrap();
+
+    for i in 0..pickups.len() {
+        let Some(pickup) = pickups[i].as_mut() else {
+            continue;
+        };
+        if pickup.ticks == 0 {
+            world.despawn(i);
+            continue;
+        }
+        pickup.ticks -= 1;
+
+        let (Some(player_coll), Some(coll)) = (colliders[player_idx], colliders[i]) else {
+            continue;
+        };
+        if !coll.collides_with(&player_coll)
+            || !touching(pos[i].unwrap(), coll, player_pos, player_coll)
+        {
+            continue;
+        }
+        match pickup.item {
+            Item::Heart => {
+                if let Some(health) = health[player_idx].as_mut() {
+                    health.heal(HALF_HEARTS * pickup.amount as i32);
+                }
+            }
+            Item::HeartContainer => {
+                if let Some(health) = health[player_idx].as_mut() {
+                    health.max += HALF_HEARTS * pickup.amount as i32;
+                    health.heal(HALF_HEARTS * pickup.amount as i32);
+                }
+            }
+            Item::Coin => player.coins += pickup.amount,
+            Item::Arrows => player.arrows += pickup.amount,
+            Item::Bombs => player.bombs += pickup.amount,
+        }
+        pickups[i] = None;
+        world.despawn(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::OutOfBounds;
+    use crate::{LEVEL_HEIGHT, LEVEL_WIDTH};
+
+    const PX: i32 = SUBPIXELS;
+    const FLOOR: u8 = 44;
+    const WALL: u8 = 19;
+
+    fn open_map() -> Tilemap {
+        let mut map = Tilemap::new(&[FLOOR; LEVEL_WIDTH * LEVEL_HEIGHT]);
+        map.out_of_bounds = OutOfBounds::Passable;
+        map
+    }
+
+    fn block() -> Collider {
+        Collider::new(15 * PX, 15 * PX)
+    }
+
+    #[test]
+    fn box_stops_flush_against_a_blocker_head_on() {
+        let blockers = [(Pos::new(32 * PX, 0), block())];
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(32 * PX, 0),
+            block(),
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!((to.x, to.y), (32 * PX - 1 - 15 * PX, 0));
+        assert_eq!(hit, Some((-1, 0)));
+        assert!(!overlaps(to, block(), blockers[0].0, blockers[0].1));
+    }
+
+    #[test]
+    fn box_stops_flush_against_a_wall_head_on() {
+        let mut map = open_map();
+        map.set(3, 1, WALL);
+        let (to, hit) = sweep(Pos::new(0, TILE), Vel::new(3 * TILE, 0), block(), &map, &[]);
+        assert_eq!(to.x, 3 * TILE - 1 - 15 * PX);
+        assert_eq!(hit, Some((-1, 0)));
+
+        let (to, hit) = sweep(
+            Pos::new(TILE, 4 * TILE),
+            Vel::new(0, -4 * TILE),
+            block(),
+            &map,
+            &[],
+        );
+        assert_eq!(to.y, 0);
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn box_grazing_a_blocker_only_stops_when_the_edges_meet() {
+        // Boxes include their far edges, so sharing an edge counts as contact.
+        let touching = [(Pos::new(32 * PX, 15 * PX), block())];
+        let (_, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(32 * PX, 0),
+            block(),
+            &open_map(),
+            &touching,
+        );
+        assert_eq!(hit, Some((-1, 0)));
+
+        let clear = [(Pos::new(32 * PX, 15 * PX + 1), block())];
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(32 * PX, 0),
+            block(),
+            &open_map(),
+            &clear,
+        );
+        assert_eq!((to.x, hit), (32 * PX, None));
+    }
+
+    #[test]
+    fn box_ignores_blockers_it_already_overlaps() {
+        let blockers = [(Pos::new(8 * PX, 0), block())];
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(32 * PX, 0),
+            block(),
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!((to.x, hit), (32 * PX, None));
+    }
+
+    #[test]
+    fn box_without_velocity_stays_put() {
+        let blockers = [(Pos::new(15 * PX, 0), block())];
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(0, 0),
+            block(),
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!((to.x, to.y, hit), (0, 0, None));
+    }
+
+    #[test]
+    fn circle_stops_at_the_exact_point_of_contact() {
+        let ball = Collider::circle(8 * PX);
+        let blockers = [(Pos::new(32 * PX, 4 * PX), block())];
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(32 * PX, 0),
+            ball,
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!(hit, Some((-1, 0)));
+        assert!(!overlaps(to, ball, blockers[0].0, blockers[0].1));
+        let next = Pos::new(to.x + 1, to.y);
+        assert!(overlaps(next, ball, blockers[0].0, blockers[0].1));
+    }
+
+    #[test]
+    fn circle_slips_past_a_corner_that_would_stop_a_box() {
+        // The corner sits inside the circle's bounding box, but outside the circle itself.
+        let blockers = [(Pos::new(32 * PX, 15 * PX), block())];
+        let ball = Collider::circle(8 * PX);
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(20 * PX, 0),
+            ball,
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!((to.x, hit), (20 * PX, None));
+
+        let square = Collider::new(16 * PX, 16 * PX);
+        let (_, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(20 * PX, 0),
+            square,
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!(hit, Some((-1, 0)));
+    }
+
+    #[test]
+    fn circle_ignores_blockers_it_already_overlaps() {
+        let ball = Collider::circle(8 * PX);
+        let blockers = [(Pos::new(4 * PX, 4 * PX), block())];
+        let (to, hit) = sweep(
+            Pos::new(0, 0),
+            Vel::new(0, 24 * PX),
+            ball,
+            &open_map(),
+            &blockers,
+        );
+        assert_eq!((to.y, hit), (24 * PX, None));
+    }
+
+    #[test]
+    fn circle_without_velocity_stays_put() {
+        let ball = Collider::circle(8 * PX);
+        let blockers = [(Pos::new(16 * PX, 0), block())];
+        let (to, hit) = sweep(Pos::new(0, 0), Vel::new(0, 0), ball, &open_map(), &blockers);
+        assert_eq!((to.x, to.y, hit), (0, 0, None));
     }
 }
@@ -1,41 +1,254 @@
 use crate::components::*;
 use crate::ecs::World;
+use crate::systems::{DEATH_TICKS, EXPLOSION_TICKS, FALL_TICKS, PICKUP_WARNING_TICKS};
 use crate::util::*;
-use crate::Context;
+use crate::{Context, LEVEL_HEIGHT, LEVEL_WIDTH, SUBPIXELS, TILE_SIZE};
 
 use itertools::izip;
 use macroquad::prelude::*;
 
+const BLINK_TICKS: u32 = 4;
+
+/// Picks the tint showing an entity's most visible status effect.
+fn status_tint(effects: &Option<StatusEffects>) -> Color {
+    let Some(effects) = effects else {
+        return WHITE;
+    };
+    if effects.has(Status::Freeze) {
+        SKYBLUE
+    } else if effects.has(Status::Burn) {
+        ORANGE
+    } else if effects.has(Status::Poison) {
+        GREEN
+    } else if effects.has(Status::Stun) {
+        YELLOW
+    } else {
+        WHITE
+    }
+}
+
 pub fn draw_sprites(world: &World<Context>, ctx: &Context) {
     let pos = world.get::<Pos>();
     let spr = world.get::<Spr>();
-    for data in izip!(pos.iter(), spr.iter()) {
-        if let (Some(pos), Some(spr)) = data {
-            draw_tile(ctx.tileset, spr.0, pos.x as f32, pos.y as f32);
+    let falling = world.get::<Falling>();
+    let invulnerable = world.get::<Invulnerable>();
+    let effects = world.get::<StatusEffects>();
+    let dying = world.get::<Dying>();
+    for (i, data) in izip!(pos.iter(), spr.iter(), falling.iter()).enumerate() {
+        // The dying player shrinks away in red.
+        if let (Some(pos), Some(spr), Some(dying)) = (data.0, data.1, &dying[i]) {
+            let (x, y) = pos.px();
+            let scale = dying.ticks as f32 / DEATH_TICKS as f32;
+            draw_tile_ex(ctx.tileset, spr.0, x as f32, y as f32, scale, RED);
+            continue;
+        }
+        // Entities recovering from a hit blink on and off.
+        if matches!(invulnerable[i], Some(Invulnerable { ticks }) if ticks / BLINK_TICKS % 2 == 1) {
+            continue;
+        }
+        let tint = status_tint(&effects[i]);
+        match data {
+            (Some(pos), Some(spr), Some(falling)) => {
+                let (x, y) = pos.px();
+                let scale = falling.ticks as f32 / FALL_TICKS as f32;
+                draw_tile_ex(ctx.tileset, spr.0, x as f32, y as f32, scale, tint);
+            }
+            (Some(pos), Some(spr), None) => {
+                let (x, y) = pos.px();
+                draw_tile_ex(ctx.tileset, spr.0, x as f32, y as f32, 1.0, tint);
+            }
+            _ => {}
         }
     }
 }
 
 pub fn draw_attack(world: &World<Context>, _ctx: &Context) {
     let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
     let player = world.get::<Player>();
-    for data in izip!(pos.iter(), player.iter()) {
-        if let (Some(pos), Some(player)) = data {
-            if player.attack {
-                draw_circle_lines(pos.x as f32 + 7.5, pos.y as f32 + 7.5, 10.0, 1.0, BLUE);
+    for data in izip!(pos.iter(), colliders.iter(), player.iter()) {
+        if let (Some(pos), Some(collider), Some(player)) = data {
+            if let Some((pos, hitbox)) = player.hitbox(*pos, *collider) {
+                let (x, y) = pos.px();
+                let scale = SUBPIXELS as f32;
+                let (w, h) = ((hitbox.w + 1) as f32 / scale, (hitbox.h + 1) as f32 / scale);
+                draw_rectangle_lines(x as f32, y as f32, w, h, 1.0, BLUE);
+            }
+        }
+    }
+}
+
+/// Draws arrows as short lines pointing the way they fly, and other shots as small dots.
+pub fn draw_projectiles(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let vel = world.get::<Vel>();
+    let projectiles = world.get::<Projectile>();
+    for data in izip!(pos.iter(), vel.iter(), projectiles.iter()) {
+        if let (Some(pos), Some(vel), Some(projectile)) = data {
+            let (x, y) = pos.px();
+            let (x, y) = (x as f32 + 1.0, y as f32 + 1.0);
+            if projectile.sticks {
+                let dir = vec2(vel.x as f32, vel.y as f32).normalize_or_zero() * 4.0;
+                draw_line(x - dir.x, y - dir.y, x + dir.x, y + dir.y, 1.0, BEIGE);
+            } else {
+                draw_circle(x, y, 2.0, ORANGE);
+            }
+        }
+    }
+}
+
+/// Draws lit bombs, flashing faster as their fuse burns down, and the blasts they leave.
+pub fn draw_bombs(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let colliders = world.get::<Collider>();
+    let bombs = world.get::<Bomb>();
+    let explosions = world.get::<Explosion>();
+    let scale = SUBPIXELS as f32;
+    for data in izip!(pos.iter(), colliders.iter(), bombs.iter()) {
+        if let (Some(pos), Some(collider), Some(bomb)) = data {
+            let (x, y) = pos.px();
+            let radius = collider.w as f32 / scale / 2.0;
+            let (x, y) = (x as f32 + radius, y as f32 + radius);
+            let flash = (bomb.fuse / 4).max(2);
+            let colour = if bomb.fuse % flash < flash / 2 {
+                RED
+            } else {
+                DARKGRAY
+            };
+            draw_circle(x, y, radius, colour);
+        }
+    }
+    for data in izip!(pos.iter(), explosions.iter()) {
+        if let (Some(pos), Some(explosion)) = data {
+            let (x, y) = pos.px();
+            let grown = 1.0 - explosion.ticks as f32 / EXPLOSION_TICKS as f32;
+            let radius = explosion.radius as f32 / scale * grown;
+            draw_circle_lines(x as f32, y as f32, radius, 2.0, ORANGE);
+        }
+    }
+}
+
+/// Draws items lying on the floor, blinking once they are about to disappear.
+pub fn draw_pickups(world: &World<Context>, ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let pickups = world.get::<Pickup>();
+    for data in izip!(pos.iter(), pickups.iter()) {
+        if let (Some(pos), Some(pickup)) = data {
+            if pickup.ticks < PICKUP_WARNING_TICKS && pickup.ticks / BLINK_TICKS % 2 == 1 {
+                continue;
             }
+            let (x, y) = pos.px();
+            let (x, y) = (x as f32, y as f32);
+            match pickup.item {
+                Item::Heart => draw_tile_scaled(ctx.tileset, 238, x - 4.0, y - 4.0, 0.5),
+                Item::HeartContainer => draw_tile(ctx.tileset, 238, x - 4.0, y - 4.0),
+                Item::Coin => draw_circle(x + 4.0, y + 4.0, 3.0, GOLD),
+                Item::Arrows => draw_line(x, y + 8.0, x + 8.0, y, 1.0, BEIGE),
+                Item::Bombs => draw_circle(x + 4.0, y + 4.0, 3.5, DARKGRAY),
+            }
+        }
+    }
+}
+
+/// Draws burning tiles as flickering flames.
+pub fn draw_fires(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let fires = world.get::<Fire>();
+    for data in izip!(pos.iter(), fires.iter()) {
+        if let (Some(pos), Some(fire)) = data {
+            let (x, y) = pos.px();
+            let colour = if fire.ticks / BLINK_TICKS % 2 == 1 {
+                RED
+            } else {
+                ORANGE
+            };
+            let size = TILE_SIZE as f32;
+            draw_triangle(
+                vec2(x as f32 + 2.0, y as f32 + size),
+                vec2(x as f32 + size - 2.0, y as f32 + size),
+                vec2(x as f32 + size / 2.0, y as f32 + 2.0),
+                colour,
+            );
+        }
+    }
+}
+
+/// Marks each checkpoint with a flag, which turns green once it is the active one.
+pub fn draw_checkpoints(world: &World<Context>, _ctx: &Context) {
+    let pos = world.get::<Pos>();
+    let checkpoints = world.get::<Checkpoint>();
+    for data in izip!(pos.iter(), checkpoints.iter()) {
+        if let (Some(pos), Some(checkpoint)) = data {
+            let (x, y) = pos.px();
+            let (x, y) = (x as f32 + 4.0, y as f32 + 2.0);
+            let colour = if checkpoint.active { GREEN } else { GRAY };
+            draw_line(x, y, x, y + 12.0, 1.0, WHITE);
+            draw_triangle(vec2(x, y), vec2(x + 8.0, y + 3.0), vec2(x, y + 6.0), colour);
         }
     }
 }
 
+const HEARTS_PER_ROW: i32 = 10;
+const HEART_SPACING: f32 = 14.0;
+
+/// Draws a heart container for every whole heart of the player's maximum health, filled in halves.
 pub fn draw_health(world: &World<Context>, ctx: &Context) {
     let player = world.get::<Player>();
     let health = world.get::<Health>();
     for data in izip!(player.iter(), health.iter()) {
         if let (Some(_player), Some(health)) = data {
-            for i in 0..5 {
-                draw_tile(ctx.tileset, if health.0 > i { 238 } else { 239 }, (i * 14) as f32, 0.0);
+            for i in 0..(health.max + HALF_HEARTS - 1) / HALF_HEARTS {
+                let x = (i % HEARTS_PER_ROW) as f32 * HEART_SPACING;
+                let y = (i / HEARTS_PER_ROW) as f32 * HEART_SPACING;
+                match health.current - i * HALF_HEARTS {
+                    HALF_HEARTS.. => draw_tile(ctx.tileset, 238, x, y),
+                    1 => {
+                        draw_tile(ctx.tileset, 239, x, y);
+                        draw_half_tile(ctx.tileset, 238, x, y);
+                    }
+                    _ => draw_tile(ctx.tileset, 239, x, y),
+                }
+            }
+        }
+    }
+}
+
+const BOSS_BAR_WIDTH: f32 = 160.0;
+const BOSS_BAR_HEIGHT: f32 = 6.0;
+
+/// Draws the health of any boss still standing as a bar along the bottom of the screen.
+pub fn draw_boss_health(world: &World<Context>, _ctx: &Context) {
+    let bosses = world.get::<Boss>();
+    let health = world.get::<Health>();
+    let screen_width = (LEVEL_WIDTH as i32 * TILE_SIZE) as f32;
+    let screen_height = (LEVEL_HEIGHT as i32 * TILE_SIZE) as f32;
+    let x = (screen_width - BOSS_BAR_WIDTH) / 2.0;
+    let mut y = screen_height - BOSS_BAR_HEIGHT - 4.0;
+    for data in izip!(bosses.iter(), health.iter()) {
+        if let (Some(boss), Some(health)) = data {
+            if health.current <= 0 {
+                continue;
             }
+            let filled = health.current as f32 / health.max.max(1) as f32;
+            draw_rectangle(x, y, BOSS_BAR_WIDTH, BOSS_BAR_HEIGHT, DARKGRAY);
+            draw_rectangle(x, y, BOSS_BAR_WIDTH * filled, BOSS_BAR_HEIGHT, RED);
+            draw_rectangle_lines(x, y, BOSS_BAR_WIDTH, BOSS_BAR_HEIGHT, 1.0, WHITE);
+            draw_text(&boss.name, x, y - 2.0, 12.0, WHITE);
+            y -= BOSS_BAR_HEIGHT + 14.0;
         }
     }
 }
+
+/// Shows the player's coins, arrows and bombs along the top right of the screen.
+pub fn draw_inventory(world: &World<Context>, _ctx: &Context) {
+    let player = world.get::<Player>();
+    for player in player.iter().flatten() {
+        let text = format!(
+            "${}  arrows {}  bombs {}",
+            player.coins, player.arrows, player.bombs
+        );
+        let width = measure_text(&text, None, 12, 1.0).width;
+        let right = (LEVEL_WIDTH as i32 * TILE_SIZE) as f32;
+        draw_text(&text, right - width - 2.0, 10.0, 12.0, WHITE);
+    }
+}
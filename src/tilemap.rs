@@ -0,0 +1,105 @@
+//! The live, editable tile grid for the current level.
+
+use crate::tiles::Tile;
+use crate::LEVEL_WIDTH;
+
+/// How the area beyond the edges of a map behaves.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutOfBounds {
+    /// Blocks movement like a wall.
+    Solid,
+    /// Lets entities wander off freely.
+    Passable,
+    /// Lets entities through the edges listed, and moves the player to the room beyond when they
+    /// leave. Edges without a room beyond are solid.
+    Exit(Exits),
+}
+
+/// The levels reached by leaving a map through each of its edges.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Exits {
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+    pub up: Option<usize>,
+    pub down: Option<usize>,
+}
+
+/// An owned copy of a level's tiles, which systems may change during play.
+#[derive(Clone)]
+pub struct Tilemap {
+    tiles: Vec<u8>,
+    pub out_of_bounds: OutOfBounds,
+}
+
+impl Tilemap {
+    /// Constructs a `Tilemap` from raw level data.
+    pub fn new(tiles: &[u8]) -> Self {
+        Self {
+            tiles: tiles.to_vec(),
+            out_of_bounds: OutOfBounds::Solid,
+        }
+    }
+
+    /// Returns all tiles in row-major order.
+    pub fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    /// Converts a grid position to an index into `tiles`, if it lies within the map.
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let width = LEVEL_WIDTH as i32;
+        let height = self.height();
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            Some((y * width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tile at a grid position, or `None` if it lies outside the map.
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<Tile> {
+        self.index(x, y).map(|idx| Tile(self.tiles[idx]))
+    }
+
+    /// Checks whether a grid position blocks movement, taking the out-of-bounds policy into
+    /// account.
+    pub fn solid_at(&self, x: i32, y: i32) -> bool {
+        match (self.tile_at(x, y), self.out_of_bounds) {
+            (Some(tile), _) => tile.solid(),
+            (None, OutOfBounds::Solid) => true,
+            (None, OutOfBounds::Passable) => false,
+            (None, OutOfBounds::Exit(_)) => self.exit_at(x, y).is_none(),
+        }
+    }
+
+    /// Returns the level reached through the edge that a grid position lies beyond, along with the
+    /// direction of travel through it, if it is outside the map and that edge leads anywhere.
+    pub fn exit_at(&self, x: i32, y: i32) -> Option<(usize, (i32, i32))> {
+        let OutOfBounds::Exit(exits) = self.out_of_bounds else {
+            return None;
+        };
+        if self.tile_at(x, y).is_some() {
+            None
+        } else if x < 0 {
+            exits.left.map(|level| (level, (-1, 0)))
+        } else if x >= LEVEL_WIDTH as i32 {
+            exits.right.map(|level| (level, (1, 0)))
+        } else if y < 0 {
+            exits.up.map(|level| (level, (0, -1)))
+        } else {
+            exits.down.map(|level| (level, (0, 1)))
+        }
+    }
+
+    /// Returns the height of the map in tiles.
+    pub fn height(&self) -> i32 {
+        (self.tiles.len() / LEVEL_WIDTH) as i32
+    }
+
+    /// Replaces the tile at a grid position. Positions outside the map are ignored.
+    pub fn set(&mut self, x: i32, y: i32, tile: u8) {
+        if let Some(idx) = self.index(x, y) {
+            self.tiles[idx] = tile;
+        }
+    }
+}
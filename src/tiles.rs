@@ -1,3 +1,5 @@
+use crate::SUBPIXELS;
+
 // The contents of this array are generated using Tiled.
 pub const SOLID: [bool; 234] = [
     true, true, true, true, true, true, false, false, false, false, false, false, false, true,
@@ -17,3 +19,101 @@ pub const SOLID: [bool; 234] = [
     true, true, true, true, true, true, true, true, true, false, true, true, true, true, true,
     true, true, true, true, true, true, true, true, true, true, true,
 ];
+
+/// A tile from the tileset, as stored in a map.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Tile(pub u8);
+
+impl Tile {
+    pub fn solid(self) -> bool {
+        // Pits are drawn as walls in the tileset, but entities have to be able to walk into them.
+        self.0 != PIT && SOLID.get(self.0 as usize).copied().unwrap_or(false)
+    }
+
+    pub fn floor(self) -> Floor {
+        floor(self.0)
+    }
+}
+
+/// The tile that entities fall into.
+pub const PIT: u8 = 77;
+/// The floor tile left behind once a block fills a pit.
+pub const FILLED_PIT: u8 = 6;
+/// A wall that bombs can blow open, leaving `OPENED_WALL` behind.
+pub const CRACKED_WALL: u8 = 5;
+pub const OPENED_WALL: u8 = 6;
+/// Floor that catches fire from anything burning on it, leaving `BURNT_GRASS` behind.
+pub const DRY_GRASS: u8 = 7;
+pub const BURNT_GRASS: u8 = 6;
+
+/// Floor tiles with special movement properties.
+pub const ICE: u8 = 241;
+pub const MUD: u8 = 242;
+pub const CONVEYOR_UP: u8 = 243;
+pub const CONVEYOR_DOWN: u8 = 244;
+pub const CONVEYOR_LEFT: u8 = 245;
+pub const CONVEYOR_RIGHT: u8 = 246;
+
+/// How a floor tile affects entities standing on it.
+#[derive(Clone, Copy)]
+pub struct Floor {
+    /// Speed lost per tick, in fixed-point units.
+    pub friction: i32,
+    /// Percentage of velocity actually travelled.
+    pub speed: i32,
+    /// Velocity added per tick, in fixed-point units.
+    pub drift: (i32, i32),
+}
+
+const NORMAL: Floor = Floor {
+    friction: SUBPIXELS,
+    speed: 100,
+    drift: (0, 0),
+};
+
+/// The floors that entries in `FLOOR` stand for: normal, ice, mud, then conveyors running up,
+/// down, left and right.
+pub const FLOORS: [Floor; 7] = [
+    NORMAL,
+    Floor {
+        friction: 0,
+        ..NORMAL
+    },
+    Floor {
+        speed: 50,
+        ..NORMAL
+    },
+    Floor {
+        drift: (0, -SUBPIXELS / 2),
+        ..NORMAL
+    },
+    Floor {
+        drift: (0, SUBPIXELS / 2),
+        ..NORMAL
+    },
+    Floor {
+        drift: (-SUBPIXELS / 2, 0),
+        ..NORMAL
+    },
+    Floor {
+        drift: (SUBPIXELS / 2, 0),
+        ..NORMAL
+    },
+];
+
+// Unlike `SOLID`, this table is maintained by hand, as the tileset has no floor properties.
+/// Which of `FLOORS` each tile behaves like.
+pub const FLOOR: [u8; 256] = {
+    let mut floor = [0; 256];
+    floor[ICE as usize] = 1;
+    floor[MUD as usize] = 2;
+    floor[CONVEYOR_UP as usize] = 3;
+    floor[CONVEYOR_DOWN as usize] = 4;
+    floor[CONVEYOR_LEFT as usize] = 5;
+    floor[CONVEYOR_RIGHT as usize] = 6;
+    floor
+};
+
+pub fn floor(tile: u8) -> Floor {
+    FLOORS[FLOOR[tile as usize] as usize]
+}
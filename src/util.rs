@@ -1,21 +1,57 @@
 use macroquad::prelude::*;
 
-use crate::components::{Collider, Pos};
-use crate::{LEVEL_WIDTH, TILE_SIZE};
+use crate::components::{Collider, Pos, Shape};
+use crate::{LEVEL_WIDTH, SUBPIXELS, TILE_SIZE};
 
 pub fn draw_tile(spritesheet: Texture2D, tile: i32, x: f32, y: f32) {
+    draw_tile_scaled(spritesheet, tile, x, y, 1.0);
+}
+
+/// Draws a tile shrunk by `scale` about the centre of its usual position.
+pub fn draw_tile_scaled(spritesheet: Texture2D, tile: i32, x: f32, y: f32, scale: f32) {
+    draw_tile_ex(spritesheet, tile, x, y, scale, WHITE);
+}
+
+/// Draws a tile shrunk by `scale` about the centre of its usual position, and tinted.
+pub fn draw_tile_ex(spritesheet: Texture2D, tile: i32, x: f32, y: f32, scale: f32, tint: Color) {
     let row_len = spritesheet.width() as i32 / TILE_SIZE;
     let (tile_x, tile_y) = (tile % row_len * TILE_SIZE, tile / row_len * TILE_SIZE);
+    let size = TILE_SIZE as f32 * scale;
+    let offset = (TILE_SIZE as f32 - size) / 2.0;
+    draw_texture_ex(
+        spritesheet,
+        x + offset,
+        y + offset,
+        tint,
+        DrawTextureParams {
+            dest_size: Some(vec2(size, size)),
+            source: Some(Rect::new(
+                tile_x as f32,
+                tile_y as f32,
+                TILE_SIZE as f32,
+                TILE_SIZE as f32,
+            )),
+            ..Default::default()
+        },
+    );
+}
+
+/// Draws just the left half of a tile, such as for a half-full heart.
+pub fn draw_half_tile(spritesheet: Texture2D, tile: i32, x: f32, y: f32) {
+    let row_len = spritesheet.width() as i32 / TILE_SIZE;
+    let (tile_x, tile_y) = (tile % row_len * TILE_SIZE, tile / row_len * TILE_SIZE);
+    let half = TILE_SIZE as f32 / 2.0;
     draw_texture_ex(
         spritesheet,
         x,
         y,
         WHITE,
         DrawTextureParams {
+            dest_size: Some(vec2(half, TILE_SIZE as f32)),
             source: Some(Rect::new(
                 tile_x as f32,
                 tile_y as f32,
-                TILE_SIZE as f32,
+                half,
                 TILE_SIZE as f32,
             )),
             ..Default::default()
@@ -31,11 +67,103 @@ pub fn draw_tiles(tiles: &[u8], spritesheet: Texture2D) {
     }
 }
 
+/// Rounds a fixed-point value to the nearest whole pixel.
+pub fn round_px(value: i32) -> i32 {
+    (value + SUBPIXELS / 2).div_euclid(SUBPIXELS)
+}
+
 pub fn aabb(pos1: Pos, coll1: Collider, pos2: Pos, coll2: Collider) -> bool {
     let Pos { x: x1, y: y1 } = pos1;
     let Pos { x: x2, y: y2 } = pos2;
-    let Collider { w: w1, h: h1 } = coll1;
-    let Collider { w: w2, h: h2 } = coll2;
+    let Collider { w: w1, h: h1, .. } = coll1;
+    let Collider { w: w2, h: h2, .. } = coll2;
 
     return x1 <= x2 + w2 && x1 + w1 >= x2 && y1 <= y2 + h2 && y1 + h1 >= y2;
 }
+
+/// Checks whether two colliders overlap, whatever their shapes.
+pub fn overlaps(pos1: Pos, coll1: Collider, pos2: Pos, coll2: Collider) -> bool {
+    match (coll1.shape, coll2.shape) {
+        (Shape::Aabb, Shape::Aabb) => aabb(pos1, coll1, pos2, coll2),
+        (Shape::Circle, Shape::Circle) => {
+            let dx = (pos1.x * 2 + coll1.w - pos2.x * 2 - coll2.w) as i64;
+            let dy = (pos1.y * 2 + coll1.h - pos2.y * 2 - coll2.h) as i64;
+            let reach = (coll1.w + coll2.w) as i64;
+            dx * dx + dy * dy <= reach * reach
+        }
+        (Shape::Circle, Shape::Aabb) => circle_box(pos1, coll1, pos2, coll2),
+        (Shape::Aabb, Shape::Circle) => circle_box(pos2, coll2, pos1, coll1),
+    }
+}
+
+/// Checks a circle against a box by measuring from the nearest point of the box to its centre.
+fn circle_box(circle_pos: Pos, circle: Collider, box_pos: Pos, rect: Collider) -> bool {
+    // Doubled coordinates keep the centre of odd-sized circles exact.
+    let cx = circle_pos.x * 2 + circle.w;
+    let cy = circle_pos.y * 2 + circle.h;
+    let nearest_x = cx.clamp(box_pos.x * 2, (box_pos.x + rect.w) * 2);
+    let nearest_y = cy.clamp(box_pos.y * 2, (box_pos.y + rect.h) * 2);
+    let (dx, dy) = ((cx - nearest_x) as i64, (cy - nearest_y) as i64);
+    dx * dx + dy * dy <= circle.w as i64 * circle.w as i64
+}
+
+/// Checks whether two colliders overlap or sit directly against each other.
+pub fn touching(pos1: Pos, coll1: Collider, pos2: Pos, coll2: Collider) -> bool {
+    let grown = Collider {
+        w: coll1.w + 2,
+        h: coll1.h + 2,
+        ..coll1
+    };
+    overlaps(Pos::new(pos1.x - 1, pos1.y - 1), grown, pos2, coll2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_sharing_an_edge_overlap() {
+        let coll = Collider::new(16, 16);
+        assert!(overlaps(Pos::new(0, 0), coll, Pos::new(16, 0), coll));
+        assert!(!overlaps(Pos::new(0, 0), coll, Pos::new(17, 0), coll));
+        assert!(overlaps(Pos::new(0, 0), coll, Pos::new(16, 16), coll));
+    }
+
+    #[test]
+    fn circles_touching_at_one_point_overlap() {
+        let coll = Collider::circle(8);
+        assert!(overlaps(Pos::new(0, 0), coll, Pos::new(16, 0), coll));
+        assert!(!overlaps(Pos::new(0, 0), coll, Pos::new(17, 0), coll));
+        // 3-4-5: centres 20 apart diagonally, against a reach of 16.
+        assert!(!overlaps(Pos::new(0, 0), coll, Pos::new(12, 16), coll));
+    }
+
+    #[test]
+    fn circle_against_box_measures_from_the_nearest_point() {
+        let circle = Collider::circle(8);
+        let rect = Collider::new(16, 16);
+        // Side on, the edges meet.
+        assert!(overlaps(Pos::new(0, 0), circle, Pos::new(16, 0), rect));
+        assert!(!overlaps(Pos::new(0, 0), circle, Pos::new(17, 0), rect));
+        // At a corner, the bounding boxes overlap but the circle misses.
+        assert!(!overlaps(Pos::new(0, 0), circle, Pos::new(14, 14), rect));
+        assert!(overlaps(Pos::new(0, 0), circle, Pos::new(13, 13), rect));
+        // The order of the shapes doesn't matter.
+        assert!(!overlaps(Pos::new(14, 14), rect, Pos::new(0, 0), circle));
+    }
+
+    #[test]
+    fn odd_sized_circles_keep_their_centre() {
+        let coll = Collider::circle(3);
+        let rect = Collider::new(4, 4);
+        assert!(overlaps(Pos::new(0, 0), coll, Pos::new(6, 1), rect));
+        assert!(!overlaps(Pos::new(0, 0), coll, Pos::new(7, 1), rect));
+    }
+
+    #[test]
+    fn touching_includes_a_one_unit_gap() {
+        let coll = Collider::new(16, 16);
+        assert!(touching(Pos::new(0, 0), coll, Pos::new(17, 0), coll));
+        assert!(!touching(Pos::new(0, 0), coll, Pos::new(18, 0), coll));
+    }
+}